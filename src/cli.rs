@@ -44,7 +44,7 @@ impl AppContext {
                 #[cfg(feature = "service")]
                 Command::Service(command) => self.run_service_command(&command).await?,
                 Command::Debug(_) => {
-                    unreachable!("debug commands are handled before config is loaded")
+                    unreachable!("debug commands are handled before the app context is created")
                 }
             },
         }
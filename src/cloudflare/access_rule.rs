@@ -0,0 +1,149 @@
+//! Partial implementation, only contains fields that we'll use
+
+use std::net::IpAddr;
+
+use reqwest::Method;
+use serde::de::IgnoredAny;
+use serde::{Deserialize, Serialize};
+
+use crate::cloudflare::client::ApiRequest;
+
+/// Where an IP Access rule applies.
+#[derive(Debug, Clone, Copy)]
+pub enum RuleScope<'a> {
+    /// A single zone, by identifier.
+    Zone(&'a str),
+    /// Every zone of an account, by identifier.
+    Account(&'a str),
+}
+
+impl RuleScope<'_> {
+    fn path(self) -> String {
+        match self {
+            RuleScope::Zone(id) => format!("/zones/{id}/firewall/access_rules/rules"),
+            RuleScope::Account(id) => format!("/accounts/{id}/firewall/access_rules/rules"),
+        }
+    }
+}
+
+#[derive(Deserialize, Debug)]
+pub struct AccessRule {
+    pub id: String,
+    pub configuration: RuleConfiguration,
+    #[serde(default)]
+    pub notes: String,
+}
+
+/// What an IP Access rule matches. We only create rules matching a single address.
+#[derive(Deserialize, Serialize, Clone, Debug)]
+pub struct RuleConfiguration {
+    /// `ip` or `ip6` for a single address; other targets are never created by cf-ddns.
+    pub target: String,
+    pub value: String,
+}
+
+impl RuleConfiguration {
+    pub fn addr(&self) -> Option<IpAddr> {
+        self.value.parse().ok()
+    }
+}
+
+impl From<IpAddr> for RuleConfiguration {
+    fn from(addr: IpAddr) -> Self {
+        Self {
+            target: rule_target(addr).to_owned(),
+            value: addr.to_string(),
+        }
+    }
+}
+
+/// The configuration target matching a single address of the same family as `addr`.
+pub const fn rule_target(addr: IpAddr) -> &'static str {
+    match addr {
+        IpAddr::V4(_) => "ip",
+        IpAddr::V6(_) => "ip6",
+    }
+}
+
+/// [List IP Access rules](https://developers.cloudflare.com/api/operations/ip-access-rules-for-a-zone-list-ip-access-rules)
+#[derive(Debug)]
+pub struct ListAccessRules<'a> {
+    pub scope: RuleScope<'a>,
+    pub params: ListAccessRulesParams<'a>,
+}
+
+#[derive(Serialize, Clone, Debug, Default)]
+pub struct ListAccessRulesParams<'a> {
+    /// Matches rules whose notes contain this string.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub notes: Option<&'a str>,
+    #[serde(
+        rename = "configuration.target",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub target: Option<&'a str>,
+}
+
+impl<'a> ApiRequest for ListAccessRules<'a> {
+    type Request = ();
+    type Query = ListAccessRulesParams<'a>;
+    type Response = Vec<AccessRule>;
+
+    fn path(&self) -> String {
+        self.scope.path()
+    }
+    fn query(&self) -> Option<&Self::Query> {
+        Some(&self.params)
+    }
+}
+
+/// [Create an IP Access rule](https://developers.cloudflare.com/api/operations/ip-access-rules-for-a-zone-create-an-ip-access-rule)
+#[derive(Debug)]
+pub struct CreateAccessRule<'a> {
+    pub scope: RuleScope<'a>,
+    pub params: CreateAccessRuleParams<'a>,
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct CreateAccessRuleParams<'a> {
+    /// The action applied to matching requests, e.g. `whitelist`.
+    pub mode: &'a str,
+    pub configuration: RuleConfiguration,
+    pub notes: &'a str,
+}
+
+impl<'a> ApiRequest for CreateAccessRule<'a> {
+    type Request = CreateAccessRuleParams<'a>;
+    type Query = ();
+    type Response = AccessRule;
+
+    fn method(&self) -> Method {
+        Method::POST
+    }
+    fn path(&self) -> String {
+        self.scope.path()
+    }
+    fn body(&self) -> Option<&Self::Request> {
+        Some(&self.params)
+    }
+}
+
+/// [Delete an IP Access rule](https://developers.cloudflare.com/api/operations/ip-access-rules-for-a-zone-delete-an-ip-access-rule)
+#[derive(Debug)]
+pub struct DeleteAccessRule<'a> {
+    pub scope: RuleScope<'a>,
+    pub identifier: &'a str,
+}
+
+impl ApiRequest for DeleteAccessRule<'_> {
+    type Request = ();
+    type Query = ();
+    type Response = IgnoredAny;
+
+    fn method(&self) -> Method {
+        Method::DELETE
+    }
+    fn path(&self) -> String {
+        format!("{}/{}", self.scope.path(), self.identifier)
+    }
+}
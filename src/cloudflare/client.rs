@@ -1,12 +1,14 @@
 //! Client exchange implementation for Cloudflare API
 
-use anyhow::Result;
-use anyhow::bail;
-use reqwest::{IntoUrl, Method};
+use anyhow::{Context, Result};
+use reqwest::Method;
 use serde::de::DeserializeOwned;
 use serde::{Deserialize, Serialize};
+use tracing::warn;
 
 use crate::cloudflare::CloudFlare;
+use crate::cloudflare::error::{CloudflareError, Envelope};
+use crate::cloudflare::rate_limit::retry_after;
 
 pub trait ApiRequest {
     type Request: Serialize;
@@ -16,7 +18,8 @@ pub trait ApiRequest {
     fn method(&self) -> Method {
         Method::GET
     }
-    fn url(&self) -> impl IntoUrl;
+    /// Endpoint path relative to the API base URL, with a leading slash.
+    fn path(&self) -> String;
     fn query(&self) -> Option<&Self::Query> {
         None
     }
@@ -25,7 +28,12 @@ pub trait ApiRequest {
     }
 }
 
-pub const BASE_URL: &str = "https://api.cloudflare.com/client/v4"; // no trailing slash
+/// Page size used when walking paginated list endpoints.
+/// 50 is the largest value accepted by every list endpoint we use (`/zones` caps at 50).
+const PER_PAGE: u32 = 50;
+
+/// How many times a single call waits out a `Retry-After` before giving up.
+const MAX_RATE_LIMIT_RETRIES: u32 = 3;
 
 // Base exchange implementation
 impl CloudFlare {
@@ -33,37 +41,219 @@ impl CloudFlare {
     where
         Api: ApiRequest,
     {
-        let mut request = self
-            .http
-            .request(api.method(), api.url())
-            .query(&api.query());
+        Ok(self.send(api, None).await?.result)
+    }
+
+    /// Calls a list endpoint and follows `result_info` until every page has been fetched,
+    /// returning the merged results.
+    pub async fn call_paginated<Api, T>(&self, api: &Api) -> Result<Vec<T>>
+    where
+        Api: ApiRequest<Response = Vec<T>>,
+        T: DeserializeOwned,
+    {
+        let mut results = Vec::new();
+        let mut page = 1;
+        loop {
+            let resp = self
+                .send(
+                    api,
+                    Some(&Pagination {
+                        page: Some(page),
+                        cursor: None,
+                        per_page: PER_PAGE,
+                    }),
+                )
+                .await?;
+            let fetched = resp.result.len();
+            results.extend(resp.result);
+            match resp.result_info.as_ref().and_then(ResultInfo::next_page) {
+                // an empty page means the server has nothing more to give,
+                // regardless of what `total_pages` claims
+                Some(next) if fetched > 0 => page = next,
+                _ => break,
+            }
+        }
+        Ok(results)
+    }
+
+    /// Like [`CloudFlare::call_paginated`], for list endpoints that page with a cursor
+    /// (`result_info.cursors.after`) instead of page numbers.
+    pub async fn call_cursor_paginated<Api, T>(&self, api: &Api) -> Result<Vec<T>>
+    where
+        Api: ApiRequest<Response = Vec<T>>,
+        T: DeserializeOwned,
+    {
+        let mut results = Vec::new();
+        let mut cursor = None;
+        loop {
+            let resp = self
+                .send(
+                    api,
+                    Some(&Pagination {
+                        page: None,
+                        cursor,
+                        per_page: PER_PAGE,
+                    }),
+                )
+                .await?;
+            let fetched = resp.result.len();
+            results.extend(resp.result);
+            match resp.result_info.and_then(ResultInfo::next_cursor) {
+                Some(next) if fetched > 0 => cursor = Some(next),
+                _ => break,
+            }
+        }
+        Ok(results)
+    }
+
+    async fn send<Api>(
+        &self,
+        api: &Api,
+        pagination: Option<&Pagination>,
+    ) -> Result<Response<Api::Response>>
+    where
+        Api: ApiRequest,
+    {
+        let mut retries = 0;
+        loop {
+            self.limiter.acquire().await;
+
+            let mut request = self
+                .http
+                .request(api.method(), format!("{}{}", self.base_url, api.path()))
+                .query(&api.query())
+                .query(&pagination);
+
+            if let Some(body) = api.body() {
+                request = request.json(body);
+            }
+            let resp = request.send().await?;
 
-        if let Some(body) = api.body() {
-            request = request.json(body);
+            if retries < MAX_RATE_LIMIT_RETRIES
+                && let Some(delay) = retry_after(&resp)
+            {
+                retries += 1;
+                warn!(
+                    "Cloudflare API responded with {}, retrying in {}s",
+                    resp.status(),
+                    delay.as_secs()
+                );
+                tokio::time::sleep(delay).await;
+                continue;
+            }
+            return Response::extract(resp).await;
         }
-        Response::extract(request.send().await?).await
     }
 }
 
+#[derive(Serialize, Debug)]
+struct Pagination {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    page: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    cursor: Option<String>,
+    per_page: u32,
+}
+
 #[derive(Deserialize, Debug)]
 struct Response<T> {
     pub result: T,
+    #[serde(default)]
+    pub result_info: Option<ResultInfo>,
+}
+
+/// Pagination details attached to list responses, either page-based or cursor-based.
+#[derive(Deserialize, Debug, Clone, Default)]
+#[serde(default)]
+struct ResultInfo {
+    page: u32,
+    total_pages: u32,
+    cursors: Option<Cursors>,
+}
+
+#[derive(Deserialize, Debug, Clone, Default)]
+struct Cursors {
+    #[serde(default)]
+    after: Option<String>,
+}
+
+impl ResultInfo {
+    fn next_cursor(self) -> Option<String> {
+        self.cursors?.after.filter(|after| !after.is_empty())
+    }
+
+    const fn next_page(&self) -> Option<u32> {
+        if self.page < self.total_pages {
+            Some(self.page + 1)
+        } else {
+            None
+        }
+    }
 }
 
 impl<T> Response<T> {
-    pub async fn extract(resp: reqwest::Response) -> Result<T>
+    pub async fn extract(resp: reqwest::Response) -> Result<Self>
     where
         T: DeserializeOwned,
     {
         let status = resp.status();
-        if status.is_success() {
-            Ok(resp.json::<Response<T>>().await?.result)
+        let body = resp.text().await?;
+        // Non-JSON bodies (e.g. edge error pages) yield an empty, unsuccessful envelope.
+        let envelope: Envelope = serde_json::from_str(&body).unwrap_or_default();
+        if status.is_success() && envelope.success {
+            serde_json::from_str(&body).context("unexpected response from Cloudflare API")
         } else {
-            bail!(
-                "Error from Cloudflare API. status: {}, response: {}",
-                status,
-                resp.text().await?,
-            )
+            Err(CloudflareError::new(status, envelope.errors, body).into())
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn result_info_next_page() -> Result<()> {
+        let resp: Response<Vec<u32>> = serde_json::from_str(
+            r#"{"success":true,"result":[1,2],"result_info":{"page":1,"per_page":2,"count":2,"total_count":5,"total_pages":3}}"#,
+        )?;
+        let info = resp.result_info.expect("result_info is present");
+        assert_eq!(info.next_page(), Some(2));
+
+        let last = ResultInfo {
+            page: 3,
+            total_pages: 3,
+            cursors: None,
+        };
+        assert_eq!(last.next_page(), None);
+
+        let empty = ResultInfo {
+            page: 1,
+            total_pages: 0,
+            cursors: None,
+        };
+        assert_eq!(empty.next_page(), None);
+        Ok(())
+    }
+
+    #[test]
+    fn result_info_next_cursor() -> Result<()> {
+        let resp: Response<Vec<u32>> = serde_json::from_str(
+            r#"{"success":true,"result":[1,2],"result_info":{"cursors":{"after":"abc","before":"xyz"}}}"#,
+        )?;
+        let info = resp.result_info.expect("result_info is present");
+        assert_eq!(info.next_page(), None);
+        assert_eq!(info.next_cursor().as_deref(), Some("abc"));
+
+        let last: ResultInfo = serde_json::from_str(r#"{"cursors":{"before":"xyz"}}"#)?;
+        assert_eq!(last.next_cursor(), None);
+        Ok(())
+    }
+
+    #[test]
+    fn result_info_optional() -> Result<()> {
+        let resp: Response<u32> = serde_json::from_str(r#"{"result":1}"#)?;
+        assert!(resp.result_info.is_none());
+        Ok(())
+    }
+}
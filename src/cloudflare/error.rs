@@ -0,0 +1,213 @@
+//! Typed errors reported by the Cloudflare API
+
+use std::fmt;
+
+use reqwest::StatusCode;
+use serde::Deserialize;
+
+/// A single entry of the `errors` array in the Cloudflare response envelope.
+#[derive(Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct ApiMessage {
+    pub code: u32,
+    pub message: String,
+}
+
+/// The parts of the `{success, errors, messages, result}` envelope that are shared
+/// by every response, regardless of the result type.
+#[derive(Deserialize, Debug, Default)]
+#[serde(default)]
+pub(super) struct Envelope {
+    pub success: bool,
+    pub errors: Vec<ApiMessage>,
+}
+
+/// An error response from the Cloudflare API.
+#[derive(Debug)]
+pub struct CloudflareError {
+    pub status: StatusCode,
+    pub errors: Vec<ApiMessage>,
+    /// Raw response body, kept for diagnostics when the envelope carries no errors
+    /// (e.g. an HTML error page from the edge).
+    body: String,
+}
+
+impl CloudflareError {
+    pub(super) const fn new(status: StatusCode, errors: Vec<ApiMessage>, body: String) -> Self {
+        Self {
+            status,
+            errors,
+            body,
+        }
+    }
+
+    /// Whether retrying the same request can never succeed.
+    ///
+    /// Client errors (invalid token, missing permission, record type conflict, ...) are
+    /// permanent, except for request timeouts and rate limiting. Server errors and
+    /// unexpected failures with a successful status are treated as transient.
+    pub fn is_permanent(&self) -> bool {
+        self.status.is_client_error()
+            && self.status != StatusCode::REQUEST_TIMEOUT
+            && self.status != StatusCode::TOO_MANY_REQUESTS
+    }
+}
+
+impl fmt::Display for CloudflareError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Error from Cloudflare API. status: {}", self.status)?;
+        if self.errors.is_empty() {
+            write!(f, ", response: {}", self.body.trim())
+        } else {
+            let errors = self
+                .errors
+                .iter()
+                .map(|e| format!("[{}] {}", e.code, e.message))
+                .collect::<Vec<_>>()
+                .join("; ");
+            write!(f, ", errors: {errors}")
+        }
+    }
+}
+
+impl std::error::Error for CloudflareError {}
+
+/// Returns `true` if `err` was caused by a [`CloudflareError`] that cannot be fixed by retrying.
+pub fn is_permanent(err: &anyhow::Error) -> bool {
+    err.chain()
+        .filter_map(|cause| cause.downcast_ref::<CloudflareError>())
+        .any(CloudflareError::is_permanent)
+}
+
+/// Returns `true` if `err` indicates that the requested endpoint does not exist,
+/// e.g. when talking to an API (or a mock of it) that lacks a newer endpoint.
+pub fn is_unsupported(err: &anyhow::Error) -> bool {
+    err.chain()
+        .filter_map(|cause| cause.downcast_ref::<CloudflareError>())
+        .any(|e| {
+            matches!(
+                e.status,
+                StatusCode::METHOD_NOT_ALLOWED | StatusCode::NOT_IMPLEMENTED
+            ) || (e.status == StatusCode::NOT_FOUND && e.errors.iter().any(|m| m.code == NO_ROUTE))
+        })
+}
+
+/// Returns `true` if `err` reports that the requested resource, e.g. a record being deleted,
+/// does not exist (as opposed to the endpoint itself, see [`is_unsupported`]).
+pub fn is_not_found(err: &anyhow::Error) -> bool {
+    err.chain()
+        .filter_map(|cause| cause.downcast_ref::<CloudflareError>())
+        .any(|e| e.status == StatusCode::NOT_FOUND && !e.errors.iter().any(|m| m.code == NO_ROUTE))
+}
+
+/// Error code reported for paths the API has no route for.
+const NO_ROUTE: u32 = 7000;
+
+/// Returns `true` if `err` was caused by a network failure or a [`CloudflareError`] that may
+/// go away on its own. Unlike [`is_permanent`], unclassified errors are not considered transient.
+pub fn is_transient(err: &anyhow::Error) -> bool {
+    err.chain().any(|cause| {
+        cause.is::<reqwest::Error>()
+            || cause
+                .downcast_ref::<CloudflareError>()
+                .is_some_and(|e| !e.is_permanent())
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use anyhow::Context;
+
+    use super::*;
+
+    fn parse(status: StatusCode, body: &str) -> CloudflareError {
+        let envelope: Envelope = serde_json::from_str(body).unwrap_or_default();
+        CloudflareError::new(status, envelope.errors, body.to_owned())
+    }
+
+    #[test]
+    fn parses_envelope_errors() {
+        let err = parse(
+            StatusCode::FORBIDDEN,
+            r#"{"success":false,"errors":[{"code":10000,"message":"Authentication error"}],"messages":[],"result":null}"#,
+        );
+        assert_eq!(
+            err.errors.iter().map(|e| e.code).collect::<Vec<_>>(),
+            [10000]
+        );
+        assert_eq!(
+            err.to_string(),
+            "Error from Cloudflare API. status: 403 Forbidden, errors: [10000] Authentication error"
+        );
+    }
+
+    #[test]
+    fn keeps_raw_body_without_envelope() {
+        let err = parse(StatusCode::BAD_GATEWAY, "<html>Bad Gateway</html>\n");
+        assert!(err.errors.is_empty());
+        assert_eq!(
+            err.to_string(),
+            "Error from Cloudflare API. status: 502 Bad Gateway, response: <html>Bad Gateway</html>"
+        );
+    }
+
+    #[test]
+    fn classifies_permanent_errors() {
+        assert!(parse(StatusCode::BAD_REQUEST, "").is_permanent());
+        assert!(parse(StatusCode::FORBIDDEN, "").is_permanent());
+        assert!(!parse(StatusCode::TOO_MANY_REQUESTS, "").is_permanent());
+        assert!(!parse(StatusCode::REQUEST_TIMEOUT, "").is_permanent());
+        assert!(!parse(StatusCode::INTERNAL_SERVER_ERROR, "").is_permanent());
+        assert!(!parse(StatusCode::OK, "").is_permanent());
+    }
+
+    #[test]
+    fn is_permanent_looks_through_context() {
+        let err = Err::<(), _>(parse(StatusCode::FORBIDDEN, ""))
+            .context("Failed to update the record")
+            .expect_err("is an error");
+        assert!(is_permanent(&err));
+
+        let err = anyhow::anyhow!("connection reset");
+        assert!(!is_permanent(&err));
+    }
+
+    #[test]
+    fn is_unsupported_requires_missing_route() {
+        let err = anyhow::Error::new(parse(
+            StatusCode::NOT_FOUND,
+            r#"{"success":false,"errors":[{"code":7000,"message":"No route for that URI"}]}"#,
+        ));
+        assert!(is_unsupported(&err));
+        let err = anyhow::Error::new(parse(
+            StatusCode::NOT_FOUND,
+            r#"{"success":false,"errors":[{"code":81044,"message":"Record does not exist."}]}"#,
+        ));
+        assert!(!is_unsupported(&err));
+        let err = anyhow::Error::new(parse(StatusCode::METHOD_NOT_ALLOWED, ""));
+        assert!(is_unsupported(&err));
+    }
+
+    #[test]
+    fn is_not_found_excludes_missing_route() {
+        let err = anyhow::Error::new(parse(
+            StatusCode::NOT_FOUND,
+            r#"{"success":false,"errors":[{"code":81044,"message":"Record does not exist."}]}"#,
+        ));
+        assert!(is_not_found(&err.context("Failed to delete the record")));
+        let err = anyhow::Error::new(parse(
+            StatusCode::NOT_FOUND,
+            r#"{"success":false,"errors":[{"code":7000,"message":"No route for that URI"}]}"#,
+        ));
+        assert!(!is_not_found(&err));
+    }
+
+    #[test]
+    fn is_transient_requires_known_cause() {
+        let err = anyhow::Error::new(parse(StatusCode::SERVICE_UNAVAILABLE, ""));
+        assert!(is_transient(&err));
+        let err = anyhow::Error::new(parse(StatusCode::FORBIDDEN, ""));
+        assert!(!is_transient(&err));
+        let err = anyhow::anyhow!("Cannot find zone: example.com");
+        assert!(!is_transient(&err));
+    }
+}
@@ -0,0 +1,72 @@
+//! Partial implementation, only contains fields that we'll use
+
+use reqwest::Method;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+use crate::cloudflare::client::ApiRequest;
+
+/// A Zero Trust Gateway DNS location.
+#[derive(Deserialize, Serialize, Clone, Debug)]
+pub struct GatewayLocation {
+    pub name: String,
+    /// Source networks identifying the location.
+    #[serde(default)]
+    pub networks: Vec<LocationNetwork>,
+    /// Everything else, sent back unchanged when the location is updated.
+    #[serde(flatten)]
+    pub other: Map<String, Value>,
+}
+
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq, Eq)]
+pub struct LocationNetwork {
+    /// Network in CIDR notation.
+    pub network: String,
+}
+
+/// [Get a Zero Trust Gateway location](https://developers.cloudflare.com/api/operations/zero-trust-gateway-locations-zero-trust-gateway-location-details)
+#[derive(Debug)]
+pub struct GetGatewayLocation<'a> {
+    pub account_identifier: &'a str,
+    pub identifier: &'a str,
+}
+
+impl ApiRequest for GetGatewayLocation<'_> {
+    type Request = ();
+    type Query = ();
+    type Response = GatewayLocation;
+
+    fn path(&self) -> String {
+        format!(
+            "/accounts/{}/gateway/locations/{}",
+            self.account_identifier, self.identifier
+        )
+    }
+}
+
+/// [Update a Zero Trust Gateway location](https://developers.cloudflare.com/api/operations/zero-trust-gateway-locations-update-zero-trust-gateway-location)
+#[derive(Debug)]
+pub struct UpdateGatewayLocation<'a> {
+    pub account_identifier: &'a str,
+    pub identifier: &'a str,
+    pub params: &'a GatewayLocation,
+}
+
+impl ApiRequest for UpdateGatewayLocation<'_> {
+    type Request = GatewayLocation;
+    type Query = ();
+    type Response = GatewayLocation;
+
+    fn method(&self) -> Method {
+        Method::PUT
+    }
+    fn path(&self) -> String {
+        format!(
+            "/accounts/{}/gateway/locations/{}",
+            self.account_identifier, self.identifier
+        )
+    }
+    fn body(&self) -> Option<&Self::Request> {
+        Some(self.params)
+    }
+}
@@ -0,0 +1,78 @@
+//! Partial implementation, only contains fields that we'll use
+
+use std::fmt::Write;
+use std::net::IpAddr;
+
+use reqwest::Method;
+use serde::Serialize;
+use serde::de::IgnoredAny;
+
+use crate::cloudflare::client::ApiRequest;
+
+/// The JSON document published to Workers KV.
+#[derive(Serialize, Clone, Debug, PartialEq, Eq)]
+pub struct AddressValue<'a> {
+    pub v4: Option<IpAddr>,
+    pub v6: Option<IpAddr>,
+    /// Unix time of the update, in seconds.
+    pub timestamp: u64,
+    pub hostname: &'a str,
+}
+
+/// [Write a key-value pair](https://developers.cloudflare.com/api/operations/workers-kv-namespace-write-key-value-pair-with-metadata)
+///
+/// The body is stored verbatim as the value, so the value is the JSON serialization of `params`.
+#[derive(Debug)]
+pub struct WriteValue<'a> {
+    pub account_identifier: &'a str,
+    pub namespace_identifier: &'a str,
+    pub key: &'a str,
+    pub params: AddressValue<'a>,
+}
+
+impl<'a> ApiRequest for WriteValue<'a> {
+    type Request = AddressValue<'a>;
+    type Query = ();
+    type Response = IgnoredAny;
+
+    fn method(&self) -> Method {
+        Method::PUT
+    }
+    fn path(&self) -> String {
+        format!(
+            "/accounts/{}/storage/kv/namespaces/{}/values/{}",
+            self.account_identifier,
+            self.namespace_identifier,
+            encode_key(self.key)
+        )
+    }
+    fn body(&self) -> Option<&Self::Request> {
+        Some(&self.params)
+    }
+}
+
+/// Percent-encodes `key` for use as a single path segment. Keys may contain any character,
+/// including `/`.
+fn encode_key(key: &str) -> String {
+    let mut encoded = String::with_capacity(key.len());
+    for byte in key.bytes() {
+        if byte.is_ascii_alphanumeric() || matches!(byte, b'-' | b'.' | b'_' | b'~') {
+            encoded.push(char::from(byte));
+        } else {
+            let _ = write!(encoded, "%{byte:02X}");
+        }
+    }
+    encoded
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn encode_key_escapes_reserved() {
+        assert_eq!(encode_key("home.v1"), "home.v1");
+        assert_eq!(encode_key("ddns/home ip"), "ddns%2Fhome%20ip");
+        assert_eq!(encode_key("é"), "%C3%A9");
+    }
+}
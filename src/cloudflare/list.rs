@@ -0,0 +1,174 @@
+//! Partial implementation, only contains fields that we'll use
+
+use std::net::IpAddr;
+
+use reqwest::Method;
+use serde::{Deserialize, Serialize};
+
+use crate::cloudflare::client::ApiRequest;
+use crate::util::network::{parse_cidr, to_cidr};
+
+#[derive(Deserialize, Debug)]
+pub struct ListItem {
+    pub id: String,
+    /// An address or CIDR range; `None` for items of non-IP lists.
+    #[serde(default)]
+    pub ip: Option<String>,
+    #[serde(default)]
+    pub comment: Option<String>,
+}
+
+impl ListItem {
+    /// The network of the item, as an address and prefix length.
+    pub fn network(&self) -> Option<(IpAddr, u8)> {
+        parse_cidr(self.ip.as_deref()?)
+    }
+}
+
+/// The item value used for `addr`. IP Lists do not accept single IPv6 addresses,
+/// so the /64 prefix containing it is used instead.
+pub fn item_ip(addr: IpAddr) -> String {
+    match addr {
+        IpAddr::V4(_) => addr.to_string(),
+        IpAddr::V6(_) => to_cidr(addr, 64),
+    }
+}
+
+/// [Get list items](https://developers.cloudflare.com/api/operations/lists-get-list-items)
+#[derive(Debug)]
+pub struct ListItems<'a> {
+    pub account_identifier: &'a str,
+    pub list_identifier: &'a str,
+}
+
+impl ApiRequest for ListItems<'_> {
+    type Request = ();
+    type Query = ();
+    type Response = Vec<ListItem>;
+
+    fn path(&self) -> String {
+        format!(
+            "/accounts/{}/rules/lists/{}/items",
+            self.account_identifier, self.list_identifier
+        )
+    }
+}
+
+/// [Create list items](https://developers.cloudflare.com/api/operations/lists-create-list-items)
+#[derive(Debug)]
+pub struct CreateListItems<'a> {
+    pub account_identifier: &'a str,
+    pub list_identifier: &'a str,
+    pub params: Vec<CreateListItemParams<'a>>,
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct CreateListItemParams<'a> {
+    pub ip: String,
+    pub comment: &'a str,
+}
+
+impl<'a> ApiRequest for CreateListItems<'a> {
+    type Request = Vec<CreateListItemParams<'a>>;
+    type Query = ();
+    type Response = BulkOperation;
+
+    fn method(&self) -> Method {
+        Method::POST
+    }
+    fn path(&self) -> String {
+        format!(
+            "/accounts/{}/rules/lists/{}/items",
+            self.account_identifier, self.list_identifier
+        )
+    }
+    fn body(&self) -> Option<&Self::Request> {
+        Some(&self.params)
+    }
+}
+
+/// [Delete list items](https://developers.cloudflare.com/api/operations/lists-delete-list-items)
+#[derive(Debug)]
+pub struct DeleteListItems<'a> {
+    pub account_identifier: &'a str,
+    pub list_identifier: &'a str,
+    pub params: DeleteListItemsParams<'a>,
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct DeleteListItemsParams<'a> {
+    pub items: Vec<ItemId<'a>>,
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct ItemId<'a> {
+    pub id: &'a str,
+}
+
+impl<'a> ApiRequest for DeleteListItems<'a> {
+    type Request = DeleteListItemsParams<'a>;
+    type Query = ();
+    type Response = BulkOperation;
+
+    fn method(&self) -> Method {
+        Method::DELETE
+    }
+    fn path(&self) -> String {
+        format!(
+            "/accounts/{}/rules/lists/{}/items",
+            self.account_identifier, self.list_identifier
+        )
+    }
+    fn body(&self) -> Option<&Self::Request> {
+        Some(&self.params)
+    }
+}
+
+/// Changes to list items are applied asynchronously, as a bulk operation.
+#[derive(Deserialize, Debug)]
+pub struct BulkOperation {
+    pub operation_id: String,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct BulkOperationStatus {
+    /// `pending`, `running`, `completed` or `failed`.
+    pub status: String,
+    #[serde(default)]
+    pub error: Option<String>,
+}
+
+/// [Get bulk operation status](https://developers.cloudflare.com/api/operations/lists-get-bulk-operation-status)
+#[derive(Debug)]
+pub struct GetBulkOperation<'a> {
+    pub account_identifier: &'a str,
+    pub operation_identifier: &'a str,
+}
+
+impl ApiRequest for GetBulkOperation<'_> {
+    type Request = ();
+    type Query = ();
+    type Response = BulkOperationStatus;
+
+    fn path(&self) -> String {
+        format!(
+            "/accounts/{}/rules/lists/bulk_operations/{}",
+            self.account_identifier, self.operation_identifier
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn item_ip_uses_ipv6_prefix() -> anyhow::Result<()> {
+        assert_eq!(item_ip("203.0.113.5".parse()?), "203.0.113.5");
+        assert_eq!(
+            item_ip("2001:db8:1:2:3:4:5:6".parse()?),
+            "2001:db8:1:2::/64"
+        );
+        Ok(())
+    }
+}
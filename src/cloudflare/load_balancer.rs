@@ -0,0 +1,77 @@
+//! Partial implementation, only contains fields that we'll use
+
+use reqwest::Method;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+use crate::cloudflare::client::ApiRequest;
+
+#[derive(Deserialize, Debug)]
+pub struct Pool {
+    #[serde(default)]
+    pub origins: Vec<Origin>,
+}
+
+/// An origin of a Load Balancer pool.
+#[derive(Deserialize, Serialize, Clone, Debug)]
+pub struct Origin {
+    pub name: String,
+    /// Address or hostname of the origin.
+    pub address: String,
+    /// Everything else, sent back unchanged when the pool is updated.
+    #[serde(flatten)]
+    pub other: Map<String, Value>,
+}
+
+/// [Pool Details](https://developers.cloudflare.com/api/operations/account-load-balancer-pools-pool-details)
+#[derive(Debug)]
+pub struct GetPool<'a> {
+    pub account_identifier: &'a str,
+    pub identifier: &'a str,
+}
+
+impl ApiRequest for GetPool<'_> {
+    type Request = ();
+    type Query = ();
+    type Response = Pool;
+
+    fn path(&self) -> String {
+        format!(
+            "/accounts/{}/load_balancers/pools/{}",
+            self.account_identifier, self.identifier
+        )
+    }
+}
+
+/// [Patch Pool](https://developers.cloudflare.com/api/operations/account-load-balancer-pools-patch-pool)
+#[derive(Debug)]
+pub struct PatchPool<'a> {
+    pub account_identifier: &'a str,
+    pub identifier: &'a str,
+    pub params: PatchPoolParams<'a>,
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct PatchPoolParams<'a> {
+    /// The full list of origins, replacing the current one.
+    pub origins: &'a [Origin],
+}
+
+impl<'a> ApiRequest for PatchPool<'a> {
+    type Request = PatchPoolParams<'a>;
+    type Query = ();
+    type Response = Pool;
+
+    fn method(&self) -> Method {
+        Method::PATCH
+    }
+    fn path(&self) -> String {
+        format!(
+            "/accounts/{}/load_balancers/pools/{}",
+            self.account_identifier, self.identifier
+        )
+    }
+    fn body(&self) -> Option<&Self::Request> {
+        Some(&self.params)
+    }
+}
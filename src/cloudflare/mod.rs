@@ -1,58 +1,165 @@
 //! Extremely simplified Cloudflare API client for own use.
 
+use std::fs::read;
 use std::net::IpAddr;
 use std::time::Duration;
 
-use anyhow::Result;
-use reqwest::ClientBuilder;
-use reqwest::header::{AUTHORIZATION, CONTENT_TYPE, HeaderMap, HeaderValue};
+use anyhow::{Context, Result, bail, ensure};
+use reqwest::header::{AUTHORIZATION, CONTENT_TYPE, HeaderMap, HeaderName, HeaderValue};
+use reqwest::{Certificate, ClientBuilder, Proxy};
 
+use crate::cloudflare::access::{AccessPolicy, GetAccessPolicy, UpdateAccessPolicy};
+use crate::cloudflare::access_rule::{
+    AccessRule, CreateAccessRule, CreateAccessRuleParams, DeleteAccessRule, ListAccessRules,
+    ListAccessRulesParams, RuleScope, rule_target,
+};
+use crate::cloudflare::gateway::{GatewayLocation, GetGatewayLocation, UpdateGatewayLocation};
+use crate::cloudflare::kv::{AddressValue, WriteValue};
+use crate::cloudflare::list::{
+    CreateListItemParams, CreateListItems, DeleteListItems, DeleteListItemsParams,
+    GetBulkOperation, ItemId, ListItem, ListItems, item_ip,
+};
+use crate::cloudflare::load_balancer::{GetPool, Origin, PatchPool, PatchPoolParams, Pool};
+use crate::cloudflare::rate_limit::RateLimiter;
 use crate::cloudflare::record::{
-    CreateDnsRecord, CreateDnsRecordParams, DnsRecord, ListDnsRecords, ListDnsRecordsParams,
-    UpdateDnsRecord, UpdateDnsRecordParams,
+    BatchDnsRecords, BatchDnsRecordsParams, BatchPatch, CreateDnsRecord, CreateDnsRecordParams,
+    DeleteDnsRecord, DnsContent, DnsRecord, ListDnsRecords, ListDnsRecordsParams, UpdateDnsRecord,
+    UpdateDnsRecordParams,
 };
-use crate::cloudflare::zone::{ListZones, Zone};
+use crate::cloudflare::user::{TokenStatus, VerifyToken};
+use crate::cloudflare::zone::{GetZone, ListZones, ListZonesParams, Zone};
+use crate::config::{ApiConfig, Auth, RecordSettings};
 
+pub mod access;
+pub mod access_rule;
 mod client;
+pub mod error;
+pub mod gateway;
+pub mod kv;
+pub mod list;
+pub mod load_balancer;
+mod rate_limit;
 pub mod record;
+pub mod user;
 pub mod zone;
 
+/// TTL for records created without an explicit `ttl`.
+const DEFAULT_TTL: u32 = 60;
+
+pub const DEFAULT_BASE_URL: &str = "https://api.cloudflare.com/client/v4";
+
 pub struct CloudFlare {
     http: reqwest::Client,
+    /// API base URL without a trailing slash.
+    base_url: String,
+    /// Whether the credentials are an API token (as opposed to a Global API Key).
+    token: bool,
+    limiter: RateLimiter,
 }
 
 // Constructors
 impl CloudFlare {
-    pub fn new(token: &str) -> Result<Self> {
-        let mut headers = HeaderMap::with_capacity(2);
-        headers.insert(
-            AUTHORIZATION,
-            HeaderValue::try_from(format!("Bearer {token}"))?,
-        );
+    pub fn new(auth: &Auth, api: &ApiConfig) -> Result<Self> {
+        let mut headers = HeaderMap::with_capacity(3);
+        match auth {
+            Auth::Token { token } => {
+                headers.insert(AUTHORIZATION, secret_header(&format!("Bearer {token}"))?);
+            }
+            Auth::GlobalKey { email, key } => {
+                headers.insert(X_AUTH_EMAIL, HeaderValue::try_from(email)?);
+                headers.insert(X_AUTH_KEY, secret_header(key)?);
+            }
+        }
         headers.insert(CONTENT_TYPE, HeaderValue::from_static("application/json"));
-        let http = ClientBuilder::new()
+        let mut builder = ClientBuilder::new()
             .default_headers(headers)
-            .timeout(Duration::from_mins(1))
-            .build()?;
-        Ok(CloudFlare { http })
+            .connect_timeout(api.connect_timeout)
+            .timeout(api.timeout);
+        if let Some(proxy) = &api.proxy {
+            builder = builder
+                .proxy(Proxy::all(proxy).with_context(|| format!("invalid API proxy: {proxy}"))?);
+        }
+        for path in &api.ca_certs {
+            let pem = read(path)
+                .with_context(|| format!("unable to read CA certificate: {}", path.display()))?;
+            let certs = Certificate::from_pem_bundle(&pem)
+                .with_context(|| format!("invalid CA certificate: {}", path.display()))?;
+            builder = builder.tls_certs_merge(certs);
+        }
+        Ok(CloudFlare {
+            http: builder.build()?,
+            base_url: api.base_url.trim_end_matches('/').to_owned(),
+            token: matches!(auth, Auth::Token { .. }),
+            limiter: RateLimiter::default(),
+        })
     }
 }
 
+const X_AUTH_EMAIL: HeaderName = HeaderName::from_static("x-auth-email");
+const X_AUTH_KEY: HeaderName = HeaderName::from_static("x-auth-key");
+
+/// Builds a header value that is redacted from `Debug` output.
+fn secret_header(value: &str) -> Result<HeaderValue> {
+    let mut value =
+        HeaderValue::try_from(value).context("credentials contain invalid characters")?;
+    value.set_sensitive(true);
+    Ok(value)
+}
+
 // Api wrappers for our actual use cases
 impl CloudFlare {
-    pub async fn list_zones(&self) -> Result<Vec<Zone>> {
-        self.call(&ListZones).await
+    /// Whether the client authenticates with an API token, which can be verified.
+    pub const fn uses_token(&self) -> bool {
+        self.token
     }
+
+    pub async fn verify_token(&self) -> Result<TokenStatus> {
+        self.call(&VerifyToken).await
+    }
+
+    pub async fn get_zone(&self, zone_id: &str) -> Result<Zone> {
+        self.call(&GetZone {
+            zone_identifier: zone_id,
+        })
+        .await
+    }
+
+    /// Lists zones visible to the token, optionally narrowed down to an exact zone name.
+    pub async fn list_zones(&self, name: Option<&str>) -> Result<Vec<Zone>> {
+        let req = ListZones {
+            params: ListZonesParams { name },
+        };
+        self.call_paginated(&req).await
+    }
+
+    /// Looks up a zone by its name, ignoring ASCII case like DNS does.
+    pub async fn find_zone(&self, name: &str) -> Result<Option<Zone>> {
+        let zones = self.list_zones(Some(&name.to_ascii_lowercase())).await?;
+        Ok(zones
+            .into_iter()
+            .find(|z| z.name.eq_ignore_ascii_case(name)))
+    }
+
     pub async fn list_records(&self, zone_id: &str, name: &str) -> Result<Vec<DnsRecord>> {
+        // we only care about A and AAAA records
+        self.list_records_of_type(zone_id, name, "A,AAAA").await
+    }
+
+    /// Lists the records of `record_type` (e.g. `HTTPS`, or `A,AAAA` for several types) at `name`.
+    pub async fn list_records_of_type(
+        &self,
+        zone_id: &str,
+        name: &str,
+        record_type: &str,
+    ) -> Result<Vec<DnsRecord>> {
         let req = ListDnsRecords {
             zone_identifier: zone_id,
             params: ListDnsRecordsParams {
-                // we only care about A and AAAA records
-                record_type: Some("A,AAAA"),
+                record_type: Some(record_type),
                 name: Some(name),
             },
         };
-        self.call(&req).await
+        self.call_paginated(&req).await
     }
 
     pub async fn create_record(
@@ -60,15 +167,11 @@ impl CloudFlare {
         zone_id: &str,
         name: &str,
         addr: IpAddr,
+        settings: &RecordSettings,
     ) -> Result<DnsRecord> {
         let req = CreateDnsRecord {
             zone_identifier: zone_id,
-            params: CreateDnsRecordParams {
-                name,
-                content: addr.into(),
-                ttl: Some(60),
-                proxied: Some(false),
-            },
+            params: create_params(name, addr.into(), settings),
         };
         self.call(&req).await
     }
@@ -79,15 +182,437 @@ impl CloudFlare {
         rec_id: &str,
         name: &str,
         addr: IpAddr,
+        settings: &RecordSettings,
+    ) -> Result<DnsRecord> {
+        let req = UpdateDnsRecord {
+            zone_identifier: zone_id,
+            identifier: rec_id,
+            params: update_params(name, addr, settings),
+        };
+        self.call(&req).await
+    }
+
+    /// Creates a record with arbitrary content, e.g. a TXT record.
+    pub async fn create_record_content(
+        &self,
+        zone_id: &str,
+        name: &str,
+        content: DnsContent,
+        settings: &RecordSettings,
+    ) -> Result<DnsRecord> {
+        let req = CreateDnsRecord {
+            zone_identifier: zone_id,
+            params: create_params(name, content, settings),
+        };
+        self.call(&req).await
+    }
+
+    /// Replaces the content of a record, leaving its other settings untouched.
+    pub async fn update_record_content(
+        &self,
+        zone_id: &str,
+        rec_id: &str,
+        name: &str,
+        content: DnsContent,
     ) -> Result<DnsRecord> {
         let req = UpdateDnsRecord {
             zone_identifier: zone_id,
             identifier: rec_id,
             params: UpdateDnsRecordParams {
                 name,
-                content: addr.into(),
+                content,
+                ttl: None,
+                proxied: None,
+                comment: None,
+                tags: None,
+            },
+        };
+        self.call(&req).await
+    }
+
+    pub async fn delete_record(&self, zone_id: &str, rec_id: &str) -> Result<()> {
+        let req = DeleteDnsRecord {
+            zone_identifier: zone_id,
+            identifier: rec_id,
+        };
+        self.call(&req).await?;
+        Ok(())
+    }
+
+    /// Applies a single change with its own request.
+    pub async fn apply_change(
+        &self,
+        zone_id: &str,
+        change: &RecordChange<'_>,
+    ) -> Result<DnsRecord> {
+        match *change {
+            RecordChange::Create {
+                name,
+                addr,
+                settings,
+            } => self.create_record(zone_id, name, addr, settings).await,
+            RecordChange::Update {
+                id,
+                name,
+                addr,
+                settings,
+            } => self.update_record(zone_id, id, name, addr, settings).await,
+        }
+    }
+
+    /// Applies all changes to a zone atomically in a single request.
+    /// Returns the resulting records in the same order as `changes`.
+    pub async fn batch_changes(
+        &self,
+        zone_id: &str,
+        changes: &[RecordChange<'_>],
+    ) -> Result<Vec<DnsRecord>> {
+        let mut params = BatchDnsRecordsParams::default();
+        for change in changes {
+            match *change {
+                RecordChange::Create {
+                    name,
+                    addr,
+                    settings,
+                } => params
+                    .posts
+                    .push(create_params(name, addr.into(), settings)),
+                RecordChange::Update {
+                    id,
+                    name,
+                    addr,
+                    settings,
+                } => params.patches.push(BatchPatch {
+                    id,
+                    params: update_params(name, addr, settings),
+                }),
+            }
+        }
+        let req = BatchDnsRecords {
+            zone_identifier: zone_id,
+            params,
+        };
+        let result = self.call(&req).await?;
+        ensure!(
+            result.posts.len() == req.params.posts.len()
+                && result.patches.len() == req.params.patches.len(),
+            "unexpected number of records in batch response"
+        );
+
+        let mut posts = result.posts.into_iter();
+        let mut patches = result.patches.into_iter();
+        Ok(changes
+            .iter()
+            .filter_map(|change| match change {
+                RecordChange::Create { .. } => posts.next(),
+                RecordChange::Update { .. } => patches.next(),
+            })
+            .collect())
+    }
+}
+
+// IP Access rules
+impl CloudFlare {
+    /// Lists the single-address rules of the same family as `addr` whose notes contain `notes`.
+    pub async fn list_access_rules(
+        &self,
+        scope: RuleScope<'_>,
+        notes: &str,
+        addr: IpAddr,
+    ) -> Result<Vec<AccessRule>> {
+        let req = ListAccessRules {
+            scope,
+            params: ListAccessRulesParams {
+                notes: Some(notes),
+                target: Some(rule_target(addr)),
+            },
+        };
+        self.call_paginated(&req).await
+    }
+
+    /// Creates a rule allowing requests from `addr`.
+    pub async fn create_access_rule(
+        &self,
+        scope: RuleScope<'_>,
+        notes: &str,
+        addr: IpAddr,
+    ) -> Result<AccessRule> {
+        let req = CreateAccessRule {
+            scope,
+            params: CreateAccessRuleParams {
+                mode: "whitelist",
+                configuration: addr.into(),
+                notes,
+            },
+        };
+        self.call(&req).await
+    }
+
+    pub async fn delete_access_rule(&self, scope: RuleScope<'_>, rule_id: &str) -> Result<()> {
+        let req = DeleteAccessRule {
+            scope,
+            identifier: rule_id,
+        };
+        self.call(&req).await?;
+        Ok(())
+    }
+}
+
+/// How many times the status of an IP List bulk operation is checked before giving up.
+const BULK_OPERATION_POLLS: u32 = 10;
+
+/// Delay between two checks of an IP List bulk operation.
+const BULK_OPERATION_POLL_DELAY: Duration = Duration::from_secs(1);
+
+// IP Lists
+impl CloudFlare {
+    pub async fn list_items(&self, account_id: &str, list_id: &str) -> Result<Vec<ListItem>> {
+        let req = ListItems {
+            account_identifier: account_id,
+            list_identifier: list_id,
+        };
+        self.call_cursor_paginated(&req).await
+    }
+
+    /// Adds an item for `addr` to the list, and waits until the change is applied.
+    pub async fn create_list_item(
+        &self,
+        account_id: &str,
+        list_id: &str,
+        addr: IpAddr,
+        comment: &str,
+    ) -> Result<()> {
+        let req = CreateListItems {
+            account_identifier: account_id,
+            list_identifier: list_id,
+            params: vec![CreateListItemParams {
+                ip: item_ip(addr),
+                comment,
+            }],
+        };
+        let operation = self.call(&req).await?;
+        self.wait_for_bulk_operation(account_id, &operation.operation_id)
+            .await
+    }
+
+    /// Removes items from the list, and waits until the change is applied.
+    pub async fn delete_list_items(
+        &self,
+        account_id: &str,
+        list_id: &str,
+        item_ids: &[&str],
+    ) -> Result<()> {
+        let req = DeleteListItems {
+            account_identifier: account_id,
+            list_identifier: list_id,
+            params: DeleteListItemsParams {
+                items: item_ids.iter().map(|&id| ItemId { id }).collect(),
             },
         };
+        let operation = self.call(&req).await?;
+        self.wait_for_bulk_operation(account_id, &operation.operation_id)
+            .await
+    }
+
+    async fn wait_for_bulk_operation(&self, account_id: &str, operation_id: &str) -> Result<()> {
+        let req = GetBulkOperation {
+            account_identifier: account_id,
+            operation_identifier: operation_id,
+        };
+        for _ in 0..BULK_OPERATION_POLLS {
+            let operation = self.call(&req).await?;
+            match operation.status.as_str() {
+                "completed" => return Ok(()),
+                "failed" => bail!(
+                    "IP List operation {operation_id} failed: {}",
+                    operation.error.as_deref().unwrap_or("unknown error")
+                ),
+                _ => tokio::time::sleep(BULK_OPERATION_POLL_DELAY).await,
+            }
+        }
+        bail!("IP List operation {operation_id} is still pending")
+    }
+}
+
+// Zero Trust Gateway locations
+impl CloudFlare {
+    pub async fn get_gateway_location(
+        &self,
+        account_id: &str,
+        location_id: &str,
+    ) -> Result<GatewayLocation> {
+        let req = GetGatewayLocation {
+            account_identifier: account_id,
+            identifier: location_id,
+        };
+        self.call(&req).await
+    }
+
+    pub async fn update_gateway_location(
+        &self,
+        account_id: &str,
+        location_id: &str,
+        location: &GatewayLocation,
+    ) -> Result<GatewayLocation> {
+        let req = UpdateGatewayLocation {
+            account_identifier: account_id,
+            identifier: location_id,
+            params: location,
+        };
+        self.call(&req).await
+    }
+}
+
+// Load Balancer pools
+impl CloudFlare {
+    pub async fn get_pool(&self, account_id: &str, pool_id: &str) -> Result<Pool> {
+        let req = GetPool {
+            account_identifier: account_id,
+            identifier: pool_id,
+        };
         self.call(&req).await
     }
+
+    /// Replaces the origins of a pool.
+    pub async fn update_pool_origins(
+        &self,
+        account_id: &str,
+        pool_id: &str,
+        origins: &[Origin],
+    ) -> Result<Pool> {
+        let req = PatchPool {
+            account_identifier: account_id,
+            identifier: pool_id,
+            params: PatchPoolParams { origins },
+        };
+        self.call(&req).await
+    }
+}
+
+// Zero Trust Access policies
+impl CloudFlare {
+    pub async fn get_access_policy(
+        &self,
+        account_id: &str,
+        app_id: &str,
+        policy_id: &str,
+    ) -> Result<AccessPolicy> {
+        let req = GetAccessPolicy {
+            account_identifier: account_id,
+            app_identifier: app_id,
+            identifier: policy_id,
+        };
+        self.call(&req).await
+    }
+
+    pub async fn update_access_policy(
+        &self,
+        account_id: &str,
+        app_id: &str,
+        policy_id: &str,
+        policy: &AccessPolicy,
+    ) -> Result<AccessPolicy> {
+        let req = UpdateAccessPolicy {
+            account_identifier: account_id,
+            app_identifier: app_id,
+            identifier: policy_id,
+            params: policy,
+        };
+        self.call(&req).await
+    }
+}
+
+// Workers KV
+impl CloudFlare {
+    pub async fn write_kv_value(
+        &self,
+        account_id: &str,
+        namespace_id: &str,
+        key: &str,
+        value: AddressValue<'_>,
+    ) -> Result<()> {
+        let req = WriteValue {
+            account_identifier: account_id,
+            namespace_identifier: namespace_id,
+            key,
+            params: value,
+        };
+        self.call(&req).await?;
+        Ok(())
+    }
+}
+
+/// A staged change to a single DNS record.
+#[derive(Debug, Clone, Copy)]
+pub enum RecordChange<'a> {
+    Create {
+        name: &'a str,
+        addr: IpAddr,
+        settings: &'a RecordSettings,
+    },
+    Update {
+        id: &'a str,
+        name: &'a str,
+        addr: IpAddr,
+        settings: &'a RecordSettings,
+    },
+}
+
+fn create_params<'a>(
+    name: &'a str,
+    content: DnsContent,
+    settings: &'a RecordSettings,
+) -> CreateDnsRecordParams<'a> {
+    CreateDnsRecordParams {
+        name,
+        content,
+        ttl: Some(settings.ttl.unwrap_or(DEFAULT_TTL)),
+        proxied: Some(settings.proxied.unwrap_or(false)),
+        comment: settings.comment.as_deref(),
+        tags: settings.tags(),
+    }
+}
+
+fn update_params<'a>(
+    name: &'a str,
+    addr: IpAddr,
+    settings: &'a RecordSettings,
+) -> UpdateDnsRecordParams<'a> {
+    // Only push the configured settings when asked to, so that changes made in the
+    // dashboard are otherwise preserved. An enforced empty comment or tag list clears them.
+    let enforced = settings.enforce.then_some(settings);
+    UpdateDnsRecordParams {
+        name,
+        content: addr.into(),
+        ttl: enforced.and_then(|s| s.ttl),
+        proxied: enforced.and_then(|s| s.proxied),
+        comment: enforced.map(|s| s.comment.as_deref().unwrap_or_default()),
+        tags: enforced.map(|s| s.tags.as_slice()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::Ipv4Addr;
+
+    use super::*;
+
+    #[test]
+    fn enforced_update_clears_comment_and_tags() -> Result<()> {
+        let addr = IpAddr::V4(Ipv4Addr::new(192, 0, 2, 1));
+        let settings = RecordSettings {
+            enforce: true,
+            ..RecordSettings::default()
+        };
+        assert_eq!(
+            serde_json::to_string(&update_params("abc.example.com", addr, &settings))?,
+            r#"{"comment":"","tags":[],"name":"abc.example.com","type":"A","content":"192.0.2.1"}"#
+        );
+        let settings = RecordSettings::default();
+        assert_eq!(
+            serde_json::to_string(&update_params("abc.example.com", addr, &settings))?,
+            r#"{"name":"abc.example.com","type":"A","content":"192.0.2.1"}"#
+        );
+        Ok(())
+    }
 }
@@ -0,0 +1,138 @@
+//! Client-side request budget and `Retry-After` handling
+
+use std::cell::RefCell;
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+
+use reqwest::StatusCode;
+use reqwest::header::RETRY_AFTER;
+use tracing::{debug, warn};
+
+/// Cloudflare's global API rate limit: 1200 requests per 5 minutes per user.
+const LIMIT: usize = 1200;
+const WINDOW: Duration = Duration::from_mins(5);
+
+/// Longest `Retry-After` we are willing to wait for inside a single call.
+/// Anything longer is surfaced as an error and left to the updater's retry schedule.
+const MAX_RETRY_AFTER: Duration = Duration::from_mins(5);
+
+/// Sliding-window request budget.
+///
+/// Requests that would exceed the budget are queued until the oldest request in the
+/// window expires, instead of being sent and rejected with a 429.
+#[derive(Debug)]
+pub(super) struct RateLimiter {
+    limit: usize,
+    window: Duration,
+    // SAFETY: the client is only used from a single thread and the borrow is never held
+    // across an await point; see `Updater` for the same reasoning.
+    sent: RefCell<VecDeque<Instant>>,
+}
+
+impl Default for RateLimiter {
+    fn default() -> Self {
+        Self::new(LIMIT, WINDOW)
+    }
+}
+
+impl RateLimiter {
+    const fn new(limit: usize, window: Duration) -> Self {
+        Self {
+            limit,
+            window,
+            sent: RefCell::new(VecDeque::new()),
+        }
+    }
+
+    /// Waits until the budget allows another request, then records it.
+    pub async fn acquire(&self) {
+        loop {
+            let wait = self.reserve(Instant::now());
+            let Some(wait) = wait else {
+                return;
+            };
+            warn!(
+                "Cloudflare API request budget exhausted ({} requests per {}s), \
+                 waiting {:.1}s",
+                self.limit,
+                self.window.as_secs(),
+                wait.as_secs_f64()
+            );
+            tokio::time::sleep(wait).await;
+        }
+    }
+
+    /// Records a request at `now` if the budget allows it, otherwise returns how long
+    /// to wait before trying again.
+    fn reserve(&self, now: Instant) -> Option<Duration> {
+        let mut sent = self.sent.borrow_mut();
+        while sent
+            .front()
+            .is_some_and(|t| now.duration_since(*t) >= self.window)
+        {
+            sent.pop_front();
+        }
+        if sent.len() < self.limit {
+            sent.push_back(now);
+            debug!(
+                "Cloudflare API budget: {}/{} requests in the last {}s",
+                sent.len(),
+                self.limit,
+                self.window.as_secs()
+            );
+            return None;
+        }
+        sent.front()
+            .map(|oldest| (*oldest + self.window).saturating_duration_since(now))
+    }
+}
+
+/// Returns the delay requested by a rate-limited (429) or unavailable (503) response,
+/// if it carries a usable `Retry-After` header.
+pub(super) fn retry_after(resp: &reqwest::Response) -> Option<Duration> {
+    if !matches!(
+        resp.status(),
+        StatusCode::TOO_MANY_REQUESTS | StatusCode::SERVICE_UNAVAILABLE
+    ) {
+        return None;
+    }
+    let value = resp.headers().get(RETRY_AFTER)?.to_str().ok()?;
+    parse_retry_after(value)
+}
+
+/// Parses the delay-seconds form of `Retry-After`; the HTTP-date form is not used by Cloudflare.
+fn parse_retry_after(value: &str) -> Option<Duration> {
+    let delay = Duration::from_secs(value.trim().parse().ok()?);
+    (delay <= MAX_RETRY_AFTER).then_some(delay)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reserve_within_budget() {
+        let limiter = RateLimiter::new(2, Duration::from_secs(10));
+        let now = Instant::now();
+        assert_eq!(limiter.reserve(now), None);
+        assert_eq!(limiter.reserve(now + Duration::from_secs(1)), None);
+        assert_eq!(
+            limiter.reserve(now + Duration::from_secs(2)),
+            Some(Duration::from_secs(8))
+        );
+        // the first request leaves the window
+        assert_eq!(limiter.reserve(now + Duration::from_secs(10)), None);
+        assert_eq!(
+            limiter.reserve(now + Duration::from_secs(10)),
+            Some(Duration::from_secs(1))
+        );
+    }
+
+    #[test]
+    fn retry_after_seconds() {
+        assert_eq!(parse_retry_after("30"), Some(Duration::from_secs(30)));
+        assert_eq!(parse_retry_after(" 0 "), Some(Duration::ZERO));
+        assert_eq!(parse_retry_after("3600"), None);
+        assert_eq!(parse_retry_after("Wed, 21 Oct 2015 07:28:00 GMT"), None);
+    }
+}
@@ -3,10 +3,11 @@
 use std::net::IpAddr::{V4, V6};
 use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
 
-use reqwest::{IntoUrl, Method};
+use reqwest::Method;
+use serde::de::IgnoredAny;
 use serde::{Deserialize, Serialize};
 
-use crate::cloudflare::client::{ApiRequest, BASE_URL};
+use crate::cloudflare::client::ApiRequest;
 use crate::cloudflare::record::DnsContent::{A, AAAA};
 
 #[derive(Deserialize, Clone, Debug)]
@@ -19,16 +20,58 @@ pub struct DnsRecord {
     /// Type of the DNS record that also holds the record value
     #[serde(flatten)]
     pub content: DnsContent,
+    /// Comments or notes about the DNS record
+    #[serde(default)]
+    pub comment: Option<String>,
+    /// Custom tags for the DNS record
+    #[serde(default)]
+    pub tags: Vec<String>,
 }
 
 /// Type of the DNS record, along with the associated value.
-/// We only care about A and AAAA records for now.
+/// We only care about A and AAAA records, the address hints of HTTPS and SVCB records,
+/// and the TXT and PTR records written by cf-ddns itself.
 #[derive(Deserialize, Serialize, Clone, Debug)]
 #[serde(tag = "type")]
 #[expect(clippy::upper_case_acronyms, reason = "match Cloudflare API spelling")]
 pub enum DnsContent {
     A { content: Ipv4Addr },
     AAAA { content: Ipv6Addr },
+    HTTPS { data: SvcbData },
+    SVCB { data: SvcbData },
+    TXT { content: String },
+    PTR { content: String },
+}
+
+impl DnsContent {
+    /// The address held by an A or AAAA record.
+    pub const fn address(&self) -> Option<IpAddr> {
+        match self {
+            A { content } => Some(V4(*content)),
+            AAAA { content } => Some(V6(*content)),
+            DnsContent::HTTPS { .. }
+            | DnsContent::SVCB { .. }
+            | DnsContent::TXT { .. }
+            | DnsContent::PTR { .. } => None,
+        }
+    }
+
+    pub const fn svcb_data(&self) -> Option<&SvcbData> {
+        match self {
+            DnsContent::HTTPS { data } | DnsContent::SVCB { data } => Some(data),
+            A { .. } | AAAA { .. } | DnsContent::TXT { .. } | DnsContent::PTR { .. } => None,
+        }
+    }
+}
+
+/// Structured value of an HTTPS or SVCB record.
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq, Eq)]
+pub struct SvcbData {
+    pub priority: u16,
+    pub target: String,
+    /// Service parameters in presentation format, e.g. `alpn="h3,h2" ipv4hint="192.0.2.1"`.
+    #[serde(default)]
+    pub value: String,
 }
 
 impl From<IpAddr> for DnsContent {
@@ -40,6 +83,45 @@ impl From<IpAddr> for DnsContent {
     }
 }
 
+/// The text of TXT record content, joining its quoted character strings.
+/// Content without quotes is taken as-is.
+pub fn txt_text(content: &str) -> String {
+    if !content.starts_with('"') {
+        return content.to_owned();
+    }
+    let mut text = String::with_capacity(content.len());
+    let mut quoted = false;
+    let mut chars = content.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' => quoted = !quoted,
+            '\\' if quoted => text.extend(chars.next()),
+            c if quoted => text.push(c),
+            _ => {}
+        }
+    }
+    text
+}
+
+/// TXT record content holding `text`, quoted and split into character strings of at most
+/// 255 bytes as DNS requires.
+pub fn txt_content(text: &str) -> String {
+    let mut content = String::with_capacity(text.len() + 2);
+    let mut len = 0;
+    for c in text.chars() {
+        if len + c.len_utf8() > 255 {
+            content.push_str("\" \"");
+            len = 0;
+        }
+        if c == '"' || c == '\\' {
+            content.push('\\');
+        }
+        content.push(c);
+        len += c.len_utf8();
+    }
+    format!("\"{content}\"")
+}
+
 /// [List DNS Records](https://developers.cloudflare.com/api/operations/dns-records-for-a-zone-list-dns-records)
 #[derive(Debug)]
 pub struct ListDnsRecords<'a> {
@@ -59,8 +141,8 @@ impl<'a> ApiRequest for ListDnsRecords<'a> {
     type Request = ();
     type Query = ListDnsRecordsParams<'a>;
     type Response = Vec<DnsRecord>;
-    fn url(&self) -> impl IntoUrl {
-        format!("{}/zones/{}/dns_records", BASE_URL, self.zone_identifier)
+    fn path(&self) -> String {
+        format!("/zones/{}/dns_records", self.zone_identifier)
     }
     fn query(&self) -> Option<&Self::Query> {
         Some(&self.params)
@@ -82,6 +164,12 @@ pub struct CreateDnsRecordParams<'a> {
     /// Whether the record is receiving the performance and security benefits of Cloudflare
     #[serde(skip_serializing_if = "Option::is_none")]
     pub proxied: Option<bool>,
+    /// Comments or notes about the DNS record
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub comment: Option<&'a str>,
+    /// Custom tags for the DNS record, in the form `name:value`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tags: Option<&'a [String]>,
     /// DNS record name
     pub name: &'a str,
     /// Type of the DNS record that also holds the record value
@@ -97,8 +185,8 @@ impl<'a> ApiRequest for CreateDnsRecord<'a> {
     fn method(&self) -> Method {
         Method::POST
     }
-    fn url(&self) -> impl IntoUrl {
-        format!("{}/zones/{}/dns_records", BASE_URL, self.zone_identifier)
+    fn path(&self) -> String {
+        format!("/zones/{}/dns_records", self.zone_identifier)
     }
     fn body(&self) -> Option<&Self::Request> {
         Some(&self.params)
@@ -115,6 +203,18 @@ pub struct UpdateDnsRecord<'a> {
 
 #[derive(Serialize, Clone, Debug)]
 pub struct UpdateDnsRecordParams<'a> {
+    /// Time to live for DNS record. Value of 1 is 'automatic'
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ttl: Option<u32>,
+    /// Whether the record is receiving the performance and security benefits of Cloudflare
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub proxied: Option<bool>,
+    /// Comments or notes about the DNS record
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub comment: Option<&'a str>,
+    /// Custom tags for the DNS record, in the form `name:value`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tags: Option<&'a [String]>,
     /// DNS record name
     pub name: &'a str,
     /// Type of the DNS record that also holds the record value
@@ -132,13 +232,159 @@ impl<'a> ApiRequest for UpdateDnsRecord<'a> {
     fn method(&self) -> Method {
         Method::PATCH
     }
-    fn url(&self) -> impl IntoUrl {
+    fn path(&self) -> String {
         format!(
-            "{}/zones/{}/dns_records/{}",
-            BASE_URL, self.zone_identifier, self.identifier
+            "/zones/{}/dns_records/{}",
+            self.zone_identifier, self.identifier
         )
     }
     fn body(&self) -> Option<&Self::Request> {
         Some(&self.params)
     }
 }
+
+/// [Delete DNS Record](https://developers.cloudflare.com/api/operations/dns-records-for-a-zone-delete-dns-record)
+#[derive(Debug)]
+pub struct DeleteDnsRecord<'a> {
+    pub zone_identifier: &'a str,
+    pub identifier: &'a str,
+}
+
+impl ApiRequest for DeleteDnsRecord<'_> {
+    type Request = ();
+    type Query = ();
+    type Response = IgnoredAny;
+
+    fn method(&self) -> Method {
+        Method::DELETE
+    }
+    fn path(&self) -> String {
+        format!(
+            "/zones/{}/dns_records/{}",
+            self.zone_identifier, self.identifier
+        )
+    }
+}
+
+/// [Batch DNS Records](https://developers.cloudflare.com/api/resources/dns/subresources/records/methods/batch/)
+///
+/// All changes are applied atomically: if any of them fails, none are applied.
+#[derive(Debug)]
+pub struct BatchDnsRecords<'a> {
+    pub zone_identifier: &'a str,
+    pub params: BatchDnsRecordsParams<'a>,
+}
+
+#[derive(Serialize, Clone, Debug, Default)]
+pub struct BatchDnsRecordsParams<'a> {
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub patches: Vec<BatchPatch<'a>>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub posts: Vec<CreateDnsRecordParams<'a>>,
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct BatchPatch<'a> {
+    /// DNS record identifier tag
+    pub id: &'a str,
+    #[serde(flatten)]
+    pub params: UpdateDnsRecordParams<'a>,
+}
+
+/// Resulting records, in the same order as the requested changes of each kind.
+#[derive(Deserialize, Clone, Debug, Default)]
+#[serde(default)]
+pub struct BatchDnsRecordsResult {
+    pub patches: Vec<DnsRecord>,
+    pub posts: Vec<DnsRecord>,
+}
+
+impl<'a> ApiRequest for BatchDnsRecords<'a> {
+    type Request = BatchDnsRecordsParams<'a>;
+    type Query = ();
+    type Response = BatchDnsRecordsResult;
+
+    fn method(&self) -> Method {
+        Method::POST
+    }
+    fn path(&self) -> String {
+        format!("/zones/{}/dns_records/batch", self.zone_identifier)
+    }
+    fn body(&self) -> Option<&Self::Request> {
+        Some(&self.params)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn update_params_skip_unset_settings() -> anyhow::Result<()> {
+        let params = UpdateDnsRecordParams {
+            ttl: None,
+            proxied: None,
+            comment: None,
+            tags: None,
+            name: "abc.example.com",
+            content: IpAddr::V4(Ipv4Addr::new(192, 0, 2, 1)).into(),
+        };
+        assert_eq!(
+            serde_json::to_string(&params)?,
+            r#"{"name":"abc.example.com","type":"A","content":"192.0.2.1"}"#
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn svcb_content_from_api() -> anyhow::Result<()> {
+        let record: DnsRecord = serde_json::from_str(
+            r#"{"id":"rec1","name":"example.com","type":"HTTPS","content":"1 . alpn=\"h3\"",
+                "data":{"priority":1,"target":".","value":"alpn=\"h3\""}}"#,
+        )?;
+        let data = record.content.svcb_data().expect("HTTPS record has data");
+        assert_eq!(data.priority, 1);
+        assert_eq!(data.target, ".");
+        assert_eq!(data.value, r#"alpn="h3""#);
+        Ok(())
+    }
+
+    #[test]
+    fn txt_content_round_trip() {
+        assert_eq!(txt_text(r#""v=spf1 " "-all""#), "v=spf1 -all");
+        assert_eq!(txt_text("v=spf1 -all"), "v=spf1 -all");
+        assert_eq!(txt_content(r#"say "hi""#), r#""say \"hi\"""#);
+        assert_eq!(txt_text(&txt_content(r#"say "hi""#)), r#"say "hi""#);
+
+        let long = "a".repeat(300);
+        let content = txt_content(&long);
+        assert_eq!(
+            content,
+            format!(r#""{}" "{}""#, "a".repeat(255), "a".repeat(45))
+        );
+        assert_eq!(txt_text(&content), long);
+    }
+
+    #[test]
+    fn batch_params_flatten_patches() -> anyhow::Result<()> {
+        let params = BatchDnsRecordsParams {
+            patches: vec![BatchPatch {
+                id: "rec1",
+                params: UpdateDnsRecordParams {
+                    ttl: None,
+                    proxied: None,
+                    comment: None,
+                    tags: None,
+                    name: "abc.example.com",
+                    content: IpAddr::V6(Ipv6Addr::LOCALHOST).into(),
+                },
+            }],
+            posts: Vec::new(),
+        };
+        assert_eq!(
+            serde_json::to_string(&params)?,
+            r#"{"patches":[{"id":"rec1","name":"abc.example.com","type":"AAAA","content":"::1"}]}"#
+        );
+        Ok(())
+    }
+}
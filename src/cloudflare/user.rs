@@ -0,0 +1,36 @@
+//! Partial implementation, only contains fields that we'll use
+
+use serde::Deserialize;
+
+use crate::cloudflare::client::ApiRequest;
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct TokenStatus {
+    /// Token identifier tag
+    pub id: String,
+    /// Status of the token, e.g. `active`, `disabled` or `expired`
+    pub status: String,
+    /// The expiration time on or after which the token becomes invalid
+    #[serde(default)]
+    pub expires_on: Option<String>,
+}
+
+impl TokenStatus {
+    pub fn is_active(&self) -> bool {
+        self.status == "active"
+    }
+}
+
+/// [Verify Token](https://developers.cloudflare.com/api/operations/user-api-tokens-verify-token)
+#[derive(Debug)]
+pub struct VerifyToken;
+
+impl ApiRequest for VerifyToken {
+    type Request = ();
+    type Query = ();
+    type Response = TokenStatus;
+
+    fn path(&self) -> String {
+        "/user/tokens/verify".to_owned()
+    }
+}
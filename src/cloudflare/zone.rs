@@ -1,27 +1,90 @@
 //! Partial implementation, only contains fields that we'll use
 
-use const_format::concatcp;
-use reqwest::IntoUrl;
 use serde::{Deserialize, Serialize};
 
-use crate::cloudflare::client::{ApiRequest, BASE_URL};
+use crate::cloudflare::client::ApiRequest;
 
 #[derive(Deserialize, Debug)]
 pub struct Zone {
     pub id: String,
     pub name: String,
+    /// Permissions the credentials have on this zone, e.g. `#dns_records:edit`.
+    /// Not reported for every kind of credential.
+    #[serde(default)]
+    pub permissions: Vec<String>,
 }
 
-/// [List Zones](https://developers.cloudflare.com/api/operations/zones-get)
-#[derive(Serialize, Debug)]
-pub struct ListZones;
+impl Zone {
+    /// Whether the zone reports DNS edit permission; `None` when permissions are not reported.
+    pub fn can_edit_dns(&self) -> Option<bool> {
+        if self.permissions.is_empty() {
+            None
+        } else {
+            Some(self.permissions.iter().any(|p| p == "#dns_records:edit"))
+        }
+    }
+}
 
-impl ApiRequest for ListZones {
+/// [Zone Details](https://developers.cloudflare.com/api/operations/zones-0-get)
+#[derive(Debug)]
+pub struct GetZone<'a> {
+    pub zone_identifier: &'a str,
+}
+
+impl ApiRequest for GetZone<'_> {
     type Request = ();
     type Query = ();
+    type Response = Zone;
+
+    fn path(&self) -> String {
+        format!("/zones/{}", self.zone_identifier)
+    }
+}
+
+/// [List Zones](https://developers.cloudflare.com/api/operations/zones-get)
+#[derive(Debug)]
+pub struct ListZones<'a> {
+    pub params: ListZonesParams<'a>,
+}
+
+#[derive(Serialize, Clone, Debug, Default)]
+pub struct ListZonesParams<'a> {
+    /// Exact zone name to match; lists every zone visible to the token when omitted.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<&'a str>,
+}
+
+impl<'a> ApiRequest for ListZones<'a> {
+    type Request = ();
+    type Query = ListZonesParams<'a>;
     type Response = Vec<Zone>;
 
-    fn url(&self) -> impl IntoUrl {
-        concatcp!(BASE_URL, "/zones")
+    fn path(&self) -> String {
+        "/zones".to_owned()
+    }
+    fn query(&self) -> Option<&Self::Query> {
+        Some(&self.params)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn can_edit_dns() -> anyhow::Result<()> {
+        let zone: Zone = serde_json::from_str(
+            r##"{"id":"1","name":"example.com","permissions":["#zone:read","#dns_records:edit"]}"##,
+        )?;
+        assert_eq!(zone.can_edit_dns(), Some(true));
+
+        let zone: Zone = serde_json::from_str(
+            r##"{"id":"1","name":"example.com","permissions":["#zone:read","#dns_records:read"]}"##,
+        )?;
+        assert_eq!(zone.can_edit_dns(), Some(false));
+
+        let zone: Zone = serde_json::from_str(r#"{"id":"1","name":"example.com"}"#)?;
+        assert_eq!(zone.can_edit_dns(), None);
+        Ok(())
     }
 }
@@ -1,3 +1,4 @@
+use std::collections::HashMap;
 use std::fmt;
 use std::marker::PhantomData;
 use std::str::FromStr;
@@ -5,10 +6,19 @@ use std::time::Duration;
 
 use serde::{Deserialize, Deserializer, de};
 
-use super::{ProviderConfig, Records, ZoneRecord};
+use super::{
+    Auth, Duplicates, OnMissing, OnMissingConfig, ProviderConfig, RecordKind, RecordSettings,
+    Records, ZoneRecord,
+};
 pub(super) const fn default_interval() -> Duration {
     Duration::from_mins(5)
 }
+pub(super) const fn default_heartbeat_interval() -> Duration {
+    Duration::from_hours(1)
+}
+pub(super) const fn default_missing_cycles() -> u32 {
+    1
+}
 pub(super) fn duration_from_secs<'de, D: Deserializer<'de>>(d: D) -> Result<Duration, D::Error> {
     Ok(Duration::from_secs(u64::deserialize(d)?))
 }
@@ -48,92 +58,188 @@ where
     d.deserialize_any(StringOrStruct(PhantomData))
 }
 
-pub(super) fn deserialize_records<'de, D: Deserializer<'de>>(d: D) -> Result<Records, D::Error> {
-    #[derive(Debug, Clone, Eq, PartialEq, Default)]
-    enum RecordLookup {
-        #[default]
-        Disabled,
-        Global,
-        Custom(ProviderConfig),
-    }
+pub(super) fn zone_tokens<'de, D: Deserializer<'de>>(
+    d: D,
+) -> Result<HashMap<String, Auth>, D::Error> {
+    let tokens = HashMap::<String, String>::deserialize(d)?;
+    Ok(tokens
+        .into_iter()
+        .map(|(zone, token)| (zone, Auth::Token { token }))
+        .collect())
+}
 
-    fn bool_or_protocol<'de, D: Deserializer<'de>>(d: D) -> Result<RecordLookup, D::Error> {
-        struct BoolOrProtocol;
+#[derive(Debug, Clone, Eq, PartialEq, Default)]
+enum RecordLookup {
+    #[default]
+    Disabled,
+    Global,
+    Custom(ProviderConfig),
+}
 
-        impl<'de> de::Visitor<'de> for BoolOrProtocol {
-            type Value = RecordLookup;
+fn bool_or_protocol<'de, D: Deserializer<'de>>(d: D) -> Result<RecordLookup, D::Error> {
+    struct BoolOrProtocol;
 
-            fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
-                f.write_str("bool or record lookup config table")
-            }
+    impl<'de> de::Visitor<'de> for BoolOrProtocol {
+        type Value = RecordLookup;
 
-            fn visit_bool<E: de::Error>(self, v: bool) -> Result<Self::Value, E> {
-                Ok(if v {
-                    RecordLookup::Global
-                } else {
-                    RecordLookup::Disabled
-                })
-            }
+        fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            f.write_str("bool or record lookup config table")
+        }
 
-            fn visit_map<M: de::MapAccess<'de>>(self, map: M) -> Result<Self::Value, M::Error> {
-                #[derive(Deserialize)]
-                struct RecordProtocolConfig {
-                    #[serde(deserialize_with = "string_or_struct")]
-                    lookup: ProviderConfig,
-                }
-                let config =
-                    RecordProtocolConfig::deserialize(de::value::MapAccessDeserializer::new(map))?;
-                Ok(RecordLookup::Custom(config.lookup))
+        fn visit_bool<E: de::Error>(self, v: bool) -> Result<Self::Value, E> {
+            Ok(if v {
+                RecordLookup::Global
+            } else {
+                RecordLookup::Disabled
+            })
+        }
+
+        fn visit_map<M: de::MapAccess<'de>>(self, map: M) -> Result<Self::Value, M::Error> {
+            #[derive(Deserialize)]
+            struct RecordProtocolConfig {
+                #[serde(deserialize_with = "string_or_struct")]
+                lookup: ProviderConfig,
             }
+            let config =
+                RecordProtocolConfig::deserialize(de::value::MapAccessDeserializer::new(map))?;
+            Ok(RecordLookup::Custom(config.lookup))
         }
+    }
+
+    d.deserialize_any(BoolOrProtocol)
+}
+
+#[derive(Deserialize)]
+struct RecordEntry {
+    name: String,
+    #[serde(default)]
+    zone: Option<String>,
+    #[serde(default)]
+    zone_id: Option<String>,
+    #[serde(default, deserialize_with = "bool_or_protocol")]
+    v4: RecordLookup,
+    #[serde(default, deserialize_with = "bool_or_protocol")]
+    v6: RecordLookup,
+    #[serde(default)]
+    adopt: bool,
+    #[serde(default)]
+    token: Option<String>,
+    #[serde(default, rename = "type")]
+    kind: RecordKind,
+    #[serde(default)]
+    reverse_zones: Vec<String>,
+    #[serde(default, deserialize_with = "string_or_struct")]
+    on_missing: OnMissingConfig,
+    #[serde(default)]
+    duplicates: Duplicates,
+    #[serde(flatten)]
+    settings: RecordSettings,
+}
 
-        d.deserialize_any(BoolOrProtocol)
+impl RecordEntry {
+    /// The record of a single address family, IPv6 if `v6` is set.
+    fn record(&self, lookup: Option<ProviderConfig>, v6: bool) -> Result<ZoneRecord, String> {
+        let on_missing: OnMissing = self
+            .on_missing
+            .for_family(v6)
+            .map_err(|e| format!("record '{}': `on_missing`: {e}", self.name))?;
+        Ok(ZoneRecord {
+            zone: self.zone.clone(),
+            zone_id: self.zone_id.clone(),
+            name: self.name.clone(),
+            lookup,
+            settings: self.settings.clone(),
+            adopt: self.adopt,
+            auth: self.token.clone().map(|token| Auth::Token { token }),
+            kind: self.kind,
+            reverse_zones: self.reverse_zones.clone(),
+            on_missing,
+            duplicates: self.duplicates,
+        })
     }
 
-    #[derive(Deserialize)]
-    struct RecordEntry {
-        name: String,
-        zone: String,
-        #[serde(default, deserialize_with = "bool_or_protocol")]
-        v4: RecordLookup,
-        #[serde(default, deserialize_with = "bool_or_protocol")]
-        v6: RecordLookup,
+    /// Rejects options that do not apply to the record `type`.
+    fn validate(&self) -> Result<(), String> {
+        if !self.reverse_zones.is_empty() && self.kind != RecordKind::Address {
+            return Err(format!(
+                "record '{}': `reverse_zones` only applies to address records",
+                self.name
+            ));
+        }
+        if let Some(zone) = self
+            .reverse_zones
+            .iter()
+            .find(|zone| !zone.ends_with(".in-addr.arpa") && !zone.ends_with(".ip6.arpa"))
+        {
+            return Err(format!(
+                "record '{}': reverse zone '{zone}' is neither under in-addr.arpa nor ip6.arpa",
+                self.name
+            ));
+        }
+        if self.on_missing != OnMissingConfig::Keep && self.kind != RecordKind::Address {
+            return Err(format!(
+                "record '{}': `on_missing` only applies to address records",
+                self.name
+            ));
+        }
+        if self.duplicates != Duplicates::Report && self.kind != RecordKind::Address {
+            return Err(format!(
+                "record '{}': `duplicates` only applies to address records",
+                self.name
+            ));
+        }
+        if let OnMissingConfig::Delete { after: 0 } | OnMissingConfig::Fallback { after: 0, .. } =
+            self.on_missing
+        {
+            return Err(format!(
+                "record '{}': `on_missing.after` must be at least 1",
+                self.name
+            ));
+        }
+        if let Some(ttl) = self.settings.ttl
+            && ttl != 1
+            && !(30..=86400).contains(&ttl)
+        {
+            return Err(format!(
+                "record '{}': `ttl` must be 1 (automatic) or between 30 and 86400, got {ttl}",
+                self.name
+            ));
+        }
+        if self.kind.rewritten_type().is_some() && self.settings != RecordSettings::default() {
+            return Err(format!(
+                "record '{}': only the addresses within existing records of this `type` are \
+                 rewritten, `ttl`, `proxied`, `comment`, `tags` and `enforce` do not apply",
+                self.name
+            ));
+        }
+        Ok(())
     }
+}
 
+pub(super) fn deserialize_records<'de, D: Deserializer<'de>>(d: D) -> Result<Records, D::Error> {
     let entries = Vec::<RecordEntry>::deserialize(d)?;
     let mut records = Records::default();
-    for rec in entries {
-        match rec.v4 {
-            RecordLookup::Global => {
-                records.v4.push(ZoneRecord {
-                    zone: rec.zone.clone(),
-                    name: rec.name.clone(),
-                    lookup: None,
-                });
-            }
+    for mut rec in entries {
+        rec.validate().map_err(de::Error::custom)?;
+        match std::mem::take(&mut rec.v4) {
+            RecordLookup::Global => records
+                .v4
+                .push(rec.record(None, false).map_err(de::Error::custom)?),
             RecordLookup::Custom(cfg) => {
-                records.v4.push(ZoneRecord {
-                    zone: rec.zone.clone(),
-                    name: rec.name.clone(),
-                    lookup: Some(cfg),
-                });
+                records
+                    .v4
+                    .push(rec.record(Some(cfg), false).map_err(de::Error::custom)?);
             }
             RecordLookup::Disabled => {}
         }
-        match rec.v6 {
-            RecordLookup::Global => {
-                records.v6.push(ZoneRecord {
-                    zone: rec.zone.clone(),
-                    name: rec.name.clone(),
-                    lookup: None,
-                });
-            }
+        match std::mem::take(&mut rec.v6) {
+            RecordLookup::Global => records
+                .v6
+                .push(rec.record(None, true).map_err(de::Error::custom)?),
             RecordLookup::Custom(cfg) => {
-                records.v6.push(ZoneRecord {
-                    zone: rec.zone.clone(),
-                    name: rec.name.clone(),
-                    lookup: Some(cfg),
-                });
+                records
+                    .v6
+                    .push(rec.record(Some(cfg), true).map_err(de::Error::custom)?);
             }
             RecordLookup::Disabled => {}
         }
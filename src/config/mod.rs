@@ -1,4 +1,7 @@
+use std::collections::HashMap;
+use std::fmt;
 use std::fs::read_to_string;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
 use std::path::{Path, PathBuf};
 use std::str::FromStr;
 use std::time::Duration;
@@ -6,17 +9,25 @@ use std::time::Duration;
 use anyhow::{Context, Result};
 use serde::Deserialize;
 
+use crate::cloudflare::DEFAULT_BASE_URL;
 use crate::current_exe;
 use crate::lookup::{ExecLookup, ICanHazIp, InterfaceLookup, Provider};
+use crate::util::network::longest_zone;
 
 mod de;
+mod target;
 
 pub use crate::util::matcher::{Ipv4Matcher, Ipv6Matcher};
+pub use target::{
+    AccessPolicyTarget, AccessRuleTarget, GatewayLocationTarget, IpListTarget,
+    LoadBalancerOriginTarget, Target, TargetKind, WorkersKvTarget,
+};
 
 /// Parsed configuration.
 #[derive(Debug, Deserialize)]
 pub struct Config {
-    pub token: String,
+    #[serde(flatten)]
+    pub auth: Auth,
     #[serde(default, deserialize_with = "de::string_or_struct")]
     pub lookup: LookupConfig,
     #[serde(
@@ -26,9 +37,23 @@ pub struct Config {
     pub interval: Duration,
     #[serde(default)]
     pub retry: RetryConfig,
+    #[serde(default)]
+    pub api: ApiConfig,
+    /// Marker that identifies records managed by cf-ddns. When set, existing records
+    /// without the marker are left alone unless their entry sets `adopt = true`.
+    #[serde(default)]
+    pub ownership: Option<OwnershipMarker>,
+    /// API tokens used instead of the global credentials for records in the given zones.
+    #[serde(default, deserialize_with = "de::zone_tokens")]
+    pub zone_tokens: HashMap<String, Auth>,
     // FIXME: remove the backward compatibility alias in a future version
     #[serde(default, alias = "zones", deserialize_with = "de::deserialize_records")]
     pub records: Records,
+    /// Cloudflare resources other than DNS records that track the current address.
+    #[serde(default)]
+    pub targets: Vec<Target>,
+    #[serde(default)]
+    pub heartbeat: Option<HeartbeatConfig>,
 }
 
 #[cfg(unix)]
@@ -74,7 +99,49 @@ impl Config {
     }
 
     pub(crate) fn from_toml(s: &str) -> Result<Self> {
-        toml::from_str(s).context("unable to parse config content")
+        let mut config: Config = toml::from_str(s).context("unable to parse config content")?;
+        // A token set on the record itself takes precedence over the one of its zone.
+        // Without a `zone`, the most specific zone of `[zone_tokens]` holding the name applies.
+        for rec in config.records.v4.iter_mut().chain(&mut config.records.v6) {
+            if rec.auth.is_none() {
+                let zone = match &rec.zone {
+                    Some(zone) => Some(zone.as_str()),
+                    None => longest_zone(config.zone_tokens.keys().map(String::as_str), &rec.name),
+                };
+                rec.auth = zone.and_then(|zone| config.zone_tokens.get(zone)).cloned();
+            }
+        }
+        if let Some(heartbeat) = &mut config.heartbeat {
+            heartbeat.auth = config.zone_tokens.get(&heartbeat.zone).cloned();
+        }
+        for target in &config.targets {
+            target.validate()?;
+        }
+        Ok(config)
+    }
+}
+
+/// Cloudflare API credentials.
+///
+/// Either an API token (`token = "..."`), or the legacy Global API Key
+/// (`email = "..."` and `key = "..."`).
+#[derive(Deserialize, Clone, Eq, PartialEq, Hash)]
+#[serde(untagged, expecting = "either `token`, or both `email` and `key`")]
+pub enum Auth {
+    Token { token: String },
+    GlobalKey { email: String, key: String },
+}
+
+// Hand-written to keep credentials out of logs and error messages.
+impl fmt::Debug for Auth {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Auth::Token { .. } => f.debug_struct("Token").finish_non_exhaustive(),
+            Auth::GlobalKey { email, .. } => f
+                .debug_struct("GlobalKey")
+                .field("email", email)
+                .finish_non_exhaustive(),
+        }
     }
 }
 
@@ -128,12 +195,20 @@ pub enum ProviderConfig {
     #[default]
     ICanHazIp,
     /// Run a shell command and parse its stdout as an IP address.
-    Exec { cmd: String },
+    Exec {
+        cmd: String,
+        /// Parse every whitespace-separated address in the output, publishing them as a set.
+        #[serde(default)]
+        all: bool,
+    },
     /// Read the address assigned to a specific network interface.
     Interface {
         interface: String,
         #[serde(default)]
         matchers: MatcherConfig,
+        /// Publish every matching address as a set, instead of the first one.
+        #[serde(default)]
+        all: bool,
     },
 }
 
@@ -159,16 +234,28 @@ impl FromStr for ProviderConfig {
 }
 
 impl ProviderConfig {
+    /// Whether the provider returns every address, which records publish as a set.
+    pub const fn returns_set(&self) -> bool {
+        match self {
+            ProviderConfig::ICanHazIp => false,
+            ProviderConfig::Exec { all, .. } | ProviderConfig::Interface { all, .. } => *all,
+        }
+    }
+
     pub fn to_provider(&self) -> Result<Provider> {
         match self {
             ProviderConfig::ICanHazIp => Ok(Provider::ICanHazIp(ICanHazIp::new()?)),
-            ProviderConfig::Exec { cmd } => Ok(Provider::Exec(ExecLookup::new(cmd.clone()))),
+            ProviderConfig::Exec { cmd, all } => {
+                Ok(Provider::Exec(ExecLookup::new(cmd.clone(), *all)))
+            }
             ProviderConfig::Interface {
                 interface,
                 matchers,
+                all,
             } => Ok(Provider::Interface(InterfaceLookup::new(
                 interface.clone(),
                 matchers.clone(),
+                *all,
             )?)),
         }
     }
@@ -193,6 +280,63 @@ impl Default for RetryConfig {
     }
 }
 
+/// TXT record publishing the last successful update, the version and the current addresses,
+/// for checking liveness with plain `dig`.
+#[derive(Debug, Clone, Deserialize)]
+pub struct HeartbeatConfig {
+    /// Full record name, e.g. `_cf-ddns.home.example.com`.
+    pub name: String,
+    pub zone: String,
+    #[serde(default)]
+    pub zone_id: Option<String>,
+    /// Minimum time between two writes while the addresses stay the same.
+    #[serde(
+        default = "de::default_heartbeat_interval",
+        deserialize_with = "de::duration_from_secs"
+    )]
+    pub min_interval: Duration,
+    /// Credentials overriding the global ones, from `[zone_tokens]`.
+    #[serde(skip)]
+    pub auth: Option<Auth>,
+}
+
+/// How records created by cf-ddns are marked as managed: with a tag, or with a string
+/// contained in the record comment.
+#[derive(Deserialize, Debug, Clone, Eq, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum OwnershipMarker {
+    Tag(String),
+    Comment(String),
+}
+
+/// Cloudflare API client settings.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct ApiConfig {
+    /// API base URL, e.g. to point at a local mock server.
+    pub base_url: String,
+    /// Proxy URL for API requests (`http://`, `https://`, `socks5://` or `socks5h://`).
+    pub proxy: Option<String>,
+    /// Extra PEM-encoded CA certificate bundles trusted in addition to the built-in roots.
+    pub ca_certs: Vec<PathBuf>,
+    #[serde(deserialize_with = "de::duration_from_secs")]
+    pub connect_timeout: Duration,
+    #[serde(deserialize_with = "de::duration_from_secs")]
+    pub timeout: Duration,
+}
+
+impl Default for ApiConfig {
+    fn default() -> Self {
+        Self {
+            base_url: DEFAULT_BASE_URL.to_owned(),
+            proxy: None,
+            ca_certs: Vec::new(),
+            connect_timeout: Duration::from_secs(10),
+            timeout: Duration::from_mins(1),
+        }
+    }
+}
+
 #[derive(Debug, Default)]
 pub struct Records {
     pub v4: Vec<ZoneRecord>,
@@ -217,10 +361,177 @@ impl Records {
 
 #[derive(Debug, Clone)]
 pub struct ZoneRecord {
-    pub zone: String,
+    /// `None` to use the most specific zone visible to the credentials that holds `name`.
+    pub zone: Option<String>,
+    /// Explicit zone identifier; skips the zone lookup when set, so tokens without
+    /// `Zone.Read` on the account still work.
+    pub zone_id: Option<String>,
     pub name: String,
     /// Per-record lookup provider override. `None` means use the global provider.
     pub lookup: Option<ProviderConfig>,
+    pub settings: RecordSettings,
+    /// Update an existing record even if it lacks the ownership marker.
+    pub adopt: bool,
+    /// Credentials overriding the global ones, from the record's `token` or `[zone_tokens]`.
+    pub auth: Option<Auth>,
+    pub kind: RecordKind,
+    /// Cloudflare-hosted `in-addr.arpa` and `ip6.arpa` zones holding the PTR records of the
+    /// record's addresses. Empty when no PTR records are maintained.
+    pub reverse_zones: Vec<String>,
+    /// What to do with the record while its address family cannot be looked up.
+    pub on_missing: OnMissing,
+    /// What to do with extra A or AAAA records found next to the managed one.
+    pub duplicates: Duplicates,
+}
+
+/// What to do with extra A or AAAA records sharing the name of a managed record, which would
+/// otherwise keep serving their stale addresses next to the updated one.
+#[derive(Deserialize, Debug, Default, Clone, Copy, Eq, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum Duplicates {
+    /// Log an error naming the extra records.
+    #[default]
+    Report,
+    /// Delete the extra records cf-ddns may manage.
+    Delete,
+}
+
+/// What to do with a record while its address family cannot be looked up, for a single family.
+#[derive(Debug, Default, Clone, Copy, Eq, PartialEq)]
+pub enum OnMissing {
+    /// Leave the record as it is.
+    #[default]
+    Keep,
+    /// Delete the record after `after` consecutive update cycles without an address.
+    Delete { after: u32 },
+    /// Point the record at `address` after `after` consecutive update cycles without an address.
+    Fallback { after: u32, address: IpAddr },
+}
+
+impl OnMissing {
+    /// Consecutive update cycles without an address before the policy applies.
+    pub const fn after(self) -> Option<u32> {
+        match self {
+            OnMissing::Keep => None,
+            OnMissing::Delete { after } | OnMissing::Fallback { after, .. } => Some(after),
+        }
+    }
+}
+
+/// `on_missing` of a record entry, covering both address families.
+///
+/// Accepts either an action name string (e.g. `"delete"`) or a table
+/// (e.g. `{ action = "fallback", v4 = "192.0.2.1" }`).
+#[derive(Deserialize, Debug, Default, Clone, Copy, Eq, PartialEq)]
+#[serde(tag = "action", rename_all = "lowercase")]
+pub enum OnMissingConfig {
+    #[default]
+    Keep,
+    Delete {
+        #[serde(default = "de::default_missing_cycles")]
+        after: u32,
+    },
+    Fallback {
+        #[serde(default = "de::default_missing_cycles")]
+        after: u32,
+        v4: Option<Ipv4Addr>,
+        v6: Option<Ipv6Addr>,
+    },
+}
+
+impl FromStr for OnMissingConfig {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "keep" => Ok(Self::Keep),
+            "delete" => Ok(Self::Delete {
+                after: de::default_missing_cycles(),
+            }),
+            "fallback" => Err(
+                r#"action "fallback" requires an address: use `{ action = "fallback", v4 = "...", v6 = "..." }`"#
+                    .to_owned(),
+            ),
+            _ => Err(format!(
+                "unknown action `{s}`, expected one of: keep, delete, fallback"
+            )),
+        }
+    }
+}
+
+impl OnMissingConfig {
+    /// The policy for IPv4 (`v6 == false`) or IPv6 records.
+    pub fn for_family(self, v6: bool) -> Result<OnMissing, String> {
+        match self {
+            OnMissingConfig::Keep => Ok(OnMissing::Keep),
+            OnMissingConfig::Delete { after } => Ok(OnMissing::Delete { after }),
+            OnMissingConfig::Fallback {
+                after,
+                v4,
+                v6: v6_addr,
+            } => {
+                let address = if v6 {
+                    v6_addr.map(IpAddr::from)
+                } else {
+                    v4.map(IpAddr::from)
+                };
+                let family = if v6 { "v6" } else { "v4" };
+                let address =
+                    address.ok_or_else(|| format!("the fallback has no `{family}` address"))?;
+                Ok(OnMissing::Fallback { after, address })
+            }
+        }
+    }
+}
+
+/// What a record entry maintains, from its `type`.
+#[derive(Deserialize, Debug, Default, Clone, Copy, Eq, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum RecordKind {
+    /// A and AAAA records holding the current addresses.
+    #[default]
+    Address,
+    /// The `ipv4hint` and `ipv6hint` parameters of existing HTTPS records.
+    Https,
+    /// The `ipv4hint` and `ipv6hint` parameters of existing SVCB records.
+    Svcb,
+    /// The `ip4:` and `ip6:` mechanisms of an existing SPF policy in a TXT record.
+    Spf,
+}
+
+impl RecordKind {
+    /// The type of the existing DNS records rewritten in place, `None` for address records,
+    /// which are created and updated as a whole.
+    pub const fn rewritten_type(self) -> Option<&'static str> {
+        match self {
+            RecordKind::Address => None,
+            RecordKind::Https => Some("HTTPS"),
+            RecordKind::Svcb => Some("SVCB"),
+            RecordKind::Spf => Some("TXT"),
+        }
+    }
+}
+
+/// DNS record settings applied when a record is created, and on every update when `enforce` is set.
+#[derive(Deserialize, Debug, Default, Clone, Eq, PartialEq)]
+#[serde(default)]
+pub struct RecordSettings {
+    /// Time to live in seconds; `1` means automatic. Defaults to 60 for created records.
+    pub ttl: Option<u32>,
+    /// Whether the record is proxied through Cloudflare. Defaults to `false` for created records.
+    pub proxied: Option<bool>,
+    pub comment: Option<String>,
+    /// Tags in the form `name:value`.
+    pub tags: Vec<String>,
+    /// Also push the settings above on every update, correcting changes made in the dashboard.
+    pub enforce: bool,
+}
+
+impl RecordSettings {
+    /// Tags to send to the API, if any are configured.
+    pub fn tags(&self) -> Option<&[String]> {
+        (!self.tags.is_empty()).then_some(self.tags.as_slice())
+    }
 }
 
 #[cfg(test)]
@@ -234,6 +545,9 @@ mod tests {
         assert_eq!(cfg.interval, Duration::from_mins(5));
         assert_eq!(cfg.retry.base_delay, Duration::from_secs(5));
         assert_eq!(cfg.retry.max_attempts, 5);
+        assert_eq!(cfg.api.base_url, DEFAULT_BASE_URL);
+        assert_eq!(cfg.api.proxy, None);
+        assert_eq!(cfg.api.timeout, Duration::from_mins(1));
         assert!(cfg.records.v4.is_empty());
         assert!(cfg.records.v6.is_empty());
         Ok(())
@@ -259,6 +573,66 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn auth_global_key() -> Result<()> {
+        let cfg = Config::from_toml(
+            r#"
+                email = "user@example.com"
+                key = "secret-global-key"
+            "#,
+        )?;
+        assert_eq!(
+            cfg.auth,
+            Auth::GlobalKey {
+                email: "user@example.com".to_owned(),
+                key: "secret-global-key".to_owned(),
+            }
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn auth_missing_errors() {
+        assert!(Config::from_toml(r#"email = "user@example.com""#).is_err());
+        assert!(Config::from_toml("interval = 60").is_err());
+    }
+
+    #[test]
+    fn auth_debug_redacts_secrets() {
+        let token = Auth::Token {
+            token: "secret-token".to_owned(),
+        };
+        assert!(!format!("{token:?}").contains("secret-token"));
+        let key = Auth::GlobalKey {
+            email: "user@example.com".to_owned(),
+            key: "secret-global-key".to_owned(),
+        };
+        let debug = format!("{key:?}");
+        assert!(debug.contains("user@example.com"));
+        assert!(!debug.contains("secret-global-key"));
+    }
+
+    #[test]
+    fn api_overridden() -> Result<()> {
+        let cfg = Config::from_toml(
+            r#"
+                token = "test"
+                [api]
+                base_url = "http://127.0.0.1:8080/client/v4"
+                proxy = "socks5h://127.0.0.1:1080"
+                ca_certs = ["/etc/ssl/corp-ca.pem"]
+                connect_timeout = 5
+                timeout = 30
+            "#,
+        )?;
+        assert_eq!(cfg.api.base_url, "http://127.0.0.1:8080/client/v4");
+        assert_eq!(cfg.api.proxy.as_deref(), Some("socks5h://127.0.0.1:1080"));
+        assert_eq!(cfg.api.ca_certs, [PathBuf::from("/etc/ssl/corp-ca.pem")]);
+        assert_eq!(cfg.api.connect_timeout, Duration::from_secs(5));
+        assert_eq!(cfg.api.timeout, Duration::from_secs(30));
+        Ok(())
+    }
+
     #[test]
     fn load_custom_config_path() -> Result<()> {
         let temp_dir = std::env::temp_dir();
@@ -266,7 +640,12 @@ mod tests {
         std::fs::write(&temp_file, r#"token = "custom_path_test_token""#)?;
 
         let cfg = Config::load(Some(&temp_file))?;
-        assert_eq!(cfg.token, "custom_path_test_token");
+        assert_eq!(
+            cfg.auth,
+            Auth::Token {
+                token: "custom_path_test_token".to_owned()
+            }
+        );
 
         let _ = std::fs::remove_file(temp_file);
         Ok(())
@@ -342,10 +721,12 @@ mod tests {
             cfg.lookup,
             LookupConfig {
                 v4: ProviderConfig::Exec {
-                    cmd: "curl -s ipv4.icanhazip.com".to_owned()
+                    cmd: "curl -s ipv4.icanhazip.com".to_owned(),
+                    all: false,
                 },
                 v6: ProviderConfig::Exec {
-                    cmd: "curl -s ipv6.icanhazip.com".to_owned()
+                    cmd: "curl -s ipv6.icanhazip.com".to_owned(),
+                    all: false,
                 },
             }
         );
@@ -368,12 +749,29 @@ mod tests {
                 v6: ProviderConfig::Interface {
                     interface: "eth0".to_owned(),
                     matchers: MatcherConfig::default(),
+                    all: false,
                 },
             }
         );
         Ok(())
     }
 
+    #[test]
+    fn lookup_all_addresses() -> Result<()> {
+        let cfg = Config::from_toml(
+            r#"
+                token = "test"
+                [lookup]
+                v4 = { provider = "exec", cmd = "wan-addresses", all = true }
+                v6 = { provider = "interface", interface = "eth0", all = true }
+            "#,
+        )?;
+        assert!(cfg.lookup.v4.returns_set());
+        assert!(cfg.lookup.v6.returns_set());
+        assert!(!ProviderConfig::ICanHazIp.returns_set());
+        Ok(())
+    }
+
     #[test]
     fn lookup_split_mixed() -> Result<()> {
         let cfg = Config::from_toml(
@@ -390,6 +788,7 @@ mod tests {
                 v4: ProviderConfig::ICanHazIp,
                 v6: ProviderConfig::Exec {
                     cmd: "dig -6 +short myip.opendns.com @resolver1.opendns.com".to_owned(),
+                    all: false,
                 },
             }
         );
@@ -480,6 +879,476 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn record_zone_id() -> Result<()> {
+        let cfg = Config::from_toml(
+            r#"
+                token = "test"
+                [[records]]
+                name = "abc.example.com"
+                zone = "example.com"
+                zone_id = "023e105f4ecef8ad9ca31a8372d0c353"
+                v4 = true
+                v6 = true
+                [[records]]
+                name = "def.example.com"
+                zone = "example.com"
+                v4 = true
+            "#,
+        )?;
+        assert_eq!(cfg.records.v4.len(), 2);
+        assert_eq!(
+            cfg.records.v4[0].zone_id.as_deref(),
+            Some("023e105f4ecef8ad9ca31a8372d0c353")
+        );
+        assert_eq!(
+            cfg.records.v6[0].zone_id.as_deref(),
+            Some("023e105f4ecef8ad9ca31a8372d0c353")
+        );
+        assert_eq!(cfg.records.v4[1].zone_id, None);
+        Ok(())
+    }
+
+    #[test]
+    fn record_settings() -> Result<()> {
+        let cfg = Config::from_toml(
+            r#"
+                token = "test"
+                [[records]]
+                name = "abc.example.com"
+                zone = "example.com"
+                ttl = 1
+                proxied = true
+                comment = "home router"
+                tags = ["owner:home"]
+                enforce = true
+                v4 = true
+                [[records]]
+                name = "def.example.com"
+                zone = "example.com"
+                v4 = true
+            "#,
+        )?;
+        assert_eq!(
+            cfg.records.v4[0].settings,
+            RecordSettings {
+                ttl: Some(1),
+                proxied: Some(true),
+                comment: Some("home router".to_owned()),
+                tags: vec!["owner:home".to_owned()],
+                enforce: true,
+            }
+        );
+        assert_eq!(cfg.records.v4[1].settings, RecordSettings::default());
+        assert_eq!(cfg.records.v4[1].settings.tags(), None);
+        Ok(())
+    }
+
+    #[test]
+    fn record_ttl_out_of_range_errors() {
+        let record = |ttl: u32| {
+            format!(
+                "token = \"test\"\n[[records]]\nname = \"abc.example.com\"\n\
+                 zone = \"example.com\"\nv4 = true\nttl = {ttl}"
+            )
+        };
+        assert!(Config::from_toml(&record(1)).is_ok());
+        assert!(Config::from_toml(&record(30)).is_ok());
+        assert!(Config::from_toml(&record(86400)).is_ok());
+        assert!(Config::from_toml(&record(0)).is_err());
+        assert!(Config::from_toml(&record(10)).is_err());
+        assert!(Config::from_toml(&record(86401)).is_err());
+    }
+
+    #[test]
+    fn ownership() -> Result<()> {
+        let cfg = Config::from_toml(
+            r#"
+                token = "test"
+                ownership = { tag = "cf-ddns:managed" }
+                [[records]]
+                name = "abc.example.com"
+                zone = "example.com"
+                adopt = true
+                v4 = true
+                [[records]]
+                name = "def.example.com"
+                zone = "example.com"
+                v4 = true
+            "#,
+        )?;
+        assert_eq!(
+            cfg.ownership,
+            Some(OwnershipMarker::Tag("cf-ddns:managed".to_owned()))
+        );
+        assert!(cfg.records.v4[0].adopt);
+        assert!(!cfg.records.v4[1].adopt);
+
+        let cfg = Config::from_toml(
+            r#"
+                token = "test"
+                [ownership]
+                comment = "cf-ddns:managed"
+            "#,
+        )?;
+        assert_eq!(
+            cfg.ownership,
+            Some(OwnershipMarker::Comment("cf-ddns:managed".to_owned()))
+        );
+        assert_eq!(Config::from_toml(r#"token = "test""#)?.ownership, None);
+        Ok(())
+    }
+
+    #[test]
+    fn access_rule_target() -> Result<()> {
+        let cfg = Config::from_toml(
+            r#"
+                token = "test"
+                [[targets]]
+                type = "access_rule"
+                zone = "example.com"
+                v4 = true
+                [[targets]]
+                type = "access_rule"
+                account_id = "account-id"
+                notes = "cf-ddns: office"
+                v4 = true
+                v6 = true
+            "#,
+        )?;
+        let [first, second] = cfg.targets.as_slice() else {
+            panic!("expected two targets, got {:?}", cfg.targets);
+        };
+        let TargetKind::AccessRule(rule) = &first.kind else {
+            panic!("expected an access rule, got {first:?}");
+        };
+        assert!(first.v4 && !first.v6);
+        assert_eq!(rule.zone.as_deref(), Some("example.com"));
+        assert_eq!(rule.notes, "cf-ddns");
+        let TargetKind::AccessRule(rule) = &second.kind else {
+            panic!("expected an access rule, got {second:?}");
+        };
+        assert!(second.v4 && second.v6);
+        assert_eq!(rule.account_id.as_deref(), Some("account-id"));
+        assert_eq!(rule.notes, "cf-ddns: office");
+
+        // the scope must be unambiguous
+        assert!(
+            Config::from_toml(
+                r#"
+                    token = "test"
+                    [[targets]]
+                    type = "access_rule"
+                    zone = "example.com"
+                    account_id = "account-id"
+                    v4 = true
+                "#,
+            )
+            .is_err()
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn ip_list_target() -> Result<()> {
+        let cfg = Config::from_toml(
+            r#"
+                token = "test"
+                [[targets]]
+                type = "ip_list"
+                account_id = "account-id"
+                list_id = "list-id"
+                comment = "cf-ddns: home"
+                v4 = true
+            "#,
+        )?;
+        let [target] = cfg.targets.as_slice() else {
+            panic!("expected one target, got {:?}", cfg.targets);
+        };
+        let TargetKind::IpList(list) = &target.kind else {
+            panic!("expected an IP List, got {target:?}");
+        };
+        assert_eq!(list.account_id, "account-id");
+        assert_eq!(list.list_id, "list-id");
+        assert_eq!(list.comment, "cf-ddns: home");
+        Ok(())
+    }
+
+    #[test]
+    fn gateway_location_target() -> Result<()> {
+        let cfg = Config::from_toml(
+            r#"
+                token = "test"
+                [[targets]]
+                type = "gateway_location"
+                account_id = "account-id"
+                location_id = "location-id"
+                v4 = true
+                v6 = true
+            "#,
+        )?;
+        let [target] = cfg.targets.as_slice() else {
+            panic!("expected one target, got {:?}", cfg.targets);
+        };
+        let TargetKind::GatewayLocation(location) = &target.kind else {
+            panic!("expected a Gateway location, got {target:?}");
+        };
+        assert_eq!(location.location_id, "location-id");
+        assert_eq!(location.ipv6_prefix_len, 64);
+        Ok(())
+    }
+
+    #[test]
+    fn load_balancer_origin_target() -> Result<()> {
+        let target = r#"
+            token = "test"
+            [[targets]]
+            type = "load_balancer_origin"
+            account_id = "account-id"
+            pool_id = "pool-id"
+            origin = "home"
+            v4 = true
+        "#;
+        let cfg = Config::from_toml(target)?;
+        let [target] = cfg.targets.as_slice() else {
+            panic!("expected one target, got {:?}", cfg.targets);
+        };
+        let TargetKind::LoadBalancerOrigin(origin) = &target.kind else {
+            panic!("expected a Load Balancer origin, got {target:?}");
+        };
+        assert_eq!(origin.pool_id, "pool-id");
+        assert_eq!(origin.origin, "home");
+
+        // an origin has a single address
+        assert!(Config::from_toml(&format!("{target}\nv6 = true")).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn workers_kv_target() -> Result<()> {
+        let target = r#"
+            token = "test"
+            [[targets]]
+            type = "workers_kv"
+            account_id = "account-id"
+            namespace_id = "namespace-id"
+            hostname = "home.example.com"
+            v4 = true
+            v6 = true
+        "#;
+        let cfg = Config::from_toml(&format!("{target}\nkey = \"home/ip\""))?;
+        let [target] = cfg.targets.as_slice() else {
+            panic!("expected one target, got {:?}", cfg.targets);
+        };
+        let TargetKind::WorkersKv(kv) = &target.kind else {
+            panic!("expected a Workers KV value, got {target:?}");
+        };
+        assert_eq!(kv.key, "home/ip");
+        assert_eq!(kv.hostname, "home.example.com");
+
+        assert!(Config::from_toml(&format!("{target}\nkey = \"\"")).is_err());
+        assert!(Config::from_toml(&format!("{target}\nkey = \"..\"")).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn duplicates() -> Result<()> {
+        let record = r#"
+            token = "test"
+            [[records]]
+            name = "home.example.com"
+            zone = "example.com"
+            v4 = true
+        "#;
+        assert_eq!(
+            Config::from_toml(record)?.records.v4[0].duplicates,
+            Duplicates::Report
+        );
+        let cfg = Config::from_toml(&format!("{record}\nduplicates = \"delete\""))?;
+        assert_eq!(cfg.records.v4[0].duplicates, Duplicates::Delete);
+        let spf = format!("{record}\ntype = \"spf\"\nduplicates = \"delete\"");
+        assert!(Config::from_toml(&spf).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn on_missing() -> Result<()> {
+        let record = r#"
+            token = "test"
+            [[records]]
+            name = "home.example.com"
+            zone = "example.com"
+            v4 = true
+            v6 = true
+        "#;
+        let cfg = Config::from_toml(record)?;
+        assert_eq!(cfg.records.v6[0].on_missing, OnMissing::Keep);
+
+        let cfg = Config::from_toml(&format!("{record}\non_missing = \"delete\""))?;
+        assert_eq!(cfg.records.v6[0].on_missing, OnMissing::Delete { after: 1 });
+
+        let cfg = Config::from_toml(&format!(
+            "{record}\non_missing = {{ action = \"fallback\", after = 3, v4 = \"192.0.2.1\", v6 = \"2001:db8::1\" }}"
+        ))?;
+        assert_eq!(
+            cfg.records.v4[0].on_missing,
+            OnMissing::Fallback {
+                after: 3,
+                address: "192.0.2.1".parse()?
+            }
+        );
+        assert_eq!(
+            cfg.records.v6[0].on_missing,
+            OnMissing::Fallback {
+                after: 3,
+                address: "2001:db8::1".parse()?
+            }
+        );
+
+        // every enabled family needs a fallback address
+        let v4_only =
+            format!("{record}\non_missing = {{ action = \"fallback\", v4 = \"192.0.2.1\" }}");
+        assert!(Config::from_toml(&v4_only).is_err());
+        assert!(Config::from_toml(&format!("{record}\non_missing = \"fallback\"")).is_err());
+        let never = format!("{record}\non_missing = {{ action = \"delete\", after = 0 }}");
+        assert!(Config::from_toml(&never).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn reverse_zones() -> Result<()> {
+        let record = r#"
+            token = "test"
+            [[records]]
+            name = "home.example.com"
+            zone = "example.com"
+            v4 = true
+        "#;
+        let cfg = Config::from_toml(&format!(
+            "{record}\nreverse_zones = [\"113.0.203.in-addr.arpa\"]"
+        ))?;
+        assert_eq!(cfg.records.v4[0].reverse_zones, ["113.0.203.in-addr.arpa"]);
+
+        assert!(
+            Config::from_toml(&format!("{record}\nreverse_zones = [\"example.net\"]")).is_err()
+        );
+        let spf = format!("{record}\ntype = \"spf\"\nreverse_zones = [\"113.0.203.in-addr.arpa\"]");
+        assert!(Config::from_toml(&spf).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn https_record_hints() -> Result<()> {
+        let record = r#"
+            token = "test"
+            [[records]]
+            name = "example.com"
+            zone = "example.com"
+            type = "https"
+            v4 = true
+            v6 = true
+        "#;
+        let cfg = Config::from_toml(record)?;
+        assert_eq!(cfg.records.v4[0].kind, RecordKind::Https);
+        assert_eq!(cfg.records.v6[0].kind, RecordKind::Https);
+
+        // only the hints are maintained
+        assert!(Config::from_toml(&format!("{record}\nttl = 60")).is_err());
+
+        let spf = record.replace(r#""https""#, r#""spf""#);
+        assert_eq!(Config::from_toml(&spf)?.records.v4[0].kind, RecordKind::Spf);
+        Ok(())
+    }
+
+    #[test]
+    fn heartbeat() -> Result<()> {
+        let cfg = Config::from_toml(
+            r#"
+                token = "test"
+                [heartbeat]
+                name = "_cf-ddns.home.example.com"
+                zone = "example.com"
+            "#,
+        )?;
+        let heartbeat = cfg.heartbeat.expect("heartbeat is configured");
+        assert_eq!(heartbeat.name, "_cf-ddns.home.example.com");
+        assert_eq!(heartbeat.min_interval, Duration::from_hours(1));
+        assert_eq!(heartbeat.auth, None);
+        assert!(Config::from_toml(r#"token = "test""#)?.heartbeat.is_none());
+        Ok(())
+    }
+
+    #[test]
+    fn token_overrides() -> Result<()> {
+        let cfg = Config::from_toml(
+            r#"
+                token = "global"
+                [zone_tokens]
+                "example.net" = "net-token"
+                [heartbeat]
+                name = "_cf-ddns.example.net"
+                zone = "example.net"
+                [[records]]
+                name = "a.example.com"
+                zone = "example.com"
+                v4 = true
+                [[records]]
+                name = "a.example.net"
+                zone = "example.net"
+                v4 = true
+                [[records]]
+                name = "b.example.net"
+                zone = "example.net"
+                token = "record-token"
+                v4 = true
+            "#,
+        )?;
+        let token = |token: &str| {
+            Some(Auth::Token {
+                token: token.to_owned(),
+            })
+        };
+        assert_eq!(cfg.records.v4[0].auth, None);
+        assert_eq!(cfg.records.v4[1].auth, token("net-token"));
+        assert_eq!(cfg.records.v4[2].auth, token("record-token"));
+        assert_eq!(
+            cfg.heartbeat.map(|heartbeat| heartbeat.auth),
+            Some(token("net-token"))
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn zone_may_be_omitted() -> Result<()> {
+        let cfg = Config::from_toml(
+            r#"
+                token = "global"
+                [zone_tokens]
+                "example.net" = "net-token"
+                "sub.example.net" = "sub-token"
+                [[records]]
+                name = "a.example.com"
+                v4 = true
+                [[records]]
+                name = "a.sub.example.net"
+                v4 = true
+                [[records]]
+                name = "a.notsub.example.net"
+                v4 = true
+            "#,
+        )?;
+        let token = |token: &str| {
+            Some(Auth::Token {
+                token: token.to_owned(),
+            })
+        };
+        assert_eq!(cfg.records.v4[0].zone, None);
+        assert_eq!(cfg.records.v4[0].auth, None);
+        assert_eq!(cfg.records.v4[1].auth, token("sub-token"));
+        assert_eq!(cfg.records.v4[2].auth, token("net-token"));
+        Ok(())
+    }
+
     #[test]
     fn record_per_record_lookup_icanhazip() -> Result<()> {
         let cfg = Config::from_toml(
@@ -529,6 +1398,7 @@ mod tests {
             Some(ProviderConfig::Interface {
                 interface: "eth0".to_owned(),
                 matchers: MatcherConfig::default(),
+                all: false,
             })
         );
         Ok(())
@@ -549,7 +1419,8 @@ mod tests {
         assert_eq!(
             cfg.records.v4[0].lookup,
             Some(ProviderConfig::Exec {
-                cmd: "curl -s ipv4.icanhazip.com".to_owned()
+                cmd: "curl -s ipv4.icanhazip.com".to_owned(),
+                all: false,
             })
         );
         Ok(())
@@ -575,6 +1446,7 @@ mod tests {
             Some(ProviderConfig::Interface {
                 interface: "eth0".to_owned(),
                 matchers: MatcherConfig::default(),
+                all: false,
             })
         );
         Ok(())
@@ -600,6 +1472,7 @@ mod tests {
                     v4: Vec::new(),
                     v6: vec!["::20/-64".parse()?],
                 },
+                all: false,
             })
         );
         Ok(())
@@ -625,6 +1498,7 @@ mod tests {
                     v4: Vec::new(),
                     v6: vec!["2001:db8::/64".parse()?],
                 },
+                all: false,
             })
         );
         Ok(())
@@ -650,6 +1524,7 @@ mod tests {
                     v4: Vec::new(),
                     v6: vec!["2001:db8::/64".parse()?, "::20/-64".parse()?,],
                 },
+                all: false,
             })
         );
         Ok(())
@@ -689,6 +1564,7 @@ mod tests {
                     v4: vec!["0.0.0.20/-24".parse()?],
                     v6: Vec::new(),
                 },
+                all: false,
             })
         );
         Ok(())
@@ -0,0 +1,241 @@
+use std::fmt;
+
+use anyhow::{Result, bail};
+use serde::Deserialize;
+
+/// A Cloudflare resource other than a DNS record that tracks the current address.
+#[derive(Deserialize, Debug, Clone)]
+pub struct Target {
+    /// Keep the target in sync with the current IPv4 address.
+    #[serde(default)]
+    pub v4: bool,
+    /// Keep the target in sync with the current IPv6 address.
+    #[serde(default)]
+    pub v6: bool,
+    #[serde(flatten)]
+    pub kind: TargetKind,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum TargetKind {
+    /// An IP Access rule allowing the current address.
+    AccessRule(AccessRuleTarget),
+    /// An item of an account IP List holding the current address.
+    IpList(IpListTarget),
+    /// The source networks of a Zero Trust Gateway DNS location.
+    GatewayLocation(GatewayLocationTarget),
+    /// The address of an origin in a Load Balancer pool.
+    LoadBalancerOrigin(LoadBalancerOriginTarget),
+    /// An `ip` include rule of a Zero Trust Access policy.
+    AccessPolicy(AccessPolicyTarget),
+    /// A Workers KV value holding the current addresses as JSON.
+    WorkersKv(WorkersKvTarget),
+}
+
+impl Target {
+    pub(super) fn validate(&self) -> Result<()> {
+        if !self.v4 && !self.v6 {
+            bail!("target {self} enables neither `v4` nor `v6`");
+        }
+        if self.v4 && self.v6 && matches!(self.kind, TargetKind::LoadBalancerOrigin(_)) {
+            bail!("target {self} holds a single address, enable either `v4` or `v6`");
+        }
+        match &self.kind {
+            TargetKind::AccessRule(rule) => rule.validate(),
+            TargetKind::IpList(list) => list.validate(),
+            TargetKind::GatewayLocation(location) => location.validate(),
+            TargetKind::WorkersKv(kv) => kv.validate(),
+            TargetKind::LoadBalancerOrigin(_) | TargetKind::AccessPolicy(_) => Ok(()),
+        }
+    }
+}
+
+impl fmt::Display for Target {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.kind {
+            TargetKind::AccessRule(rule) => rule.fmt(f),
+            TargetKind::IpList(list) => list.fmt(f),
+            TargetKind::GatewayLocation(location) => location.fmt(f),
+            TargetKind::LoadBalancerOrigin(origin) => origin.fmt(f),
+            TargetKind::AccessPolicy(policy) => policy.fmt(f),
+            TargetKind::WorkersKv(kv) => kv.fmt(f),
+        }
+    }
+}
+
+/// IP Access rule with mode `whitelist`, at zone or account level.
+///
+/// The rules managed by cf-ddns are identified by their `notes`.
+#[derive(Deserialize, Debug, Clone)]
+pub struct AccessRuleTarget {
+    /// Zone the rule applies to, looked up by name.
+    #[serde(default)]
+    pub zone: Option<String>,
+    /// Zone the rule applies to, by identifier.
+    #[serde(default)]
+    pub zone_id: Option<String>,
+    /// Account the rule applies to, covering every zone of the account.
+    #[serde(default)]
+    pub account_id: Option<String>,
+    #[serde(default = "default_marker")]
+    pub notes: String,
+}
+
+/// Identifies the resources managed by cf-ddns unless configured otherwise.
+fn default_marker() -> String {
+    "cf-ddns".to_owned()
+}
+
+impl AccessRuleTarget {
+    fn validate(&self) -> Result<()> {
+        let scopes = [&self.zone, &self.zone_id, &self.account_id];
+        if scopes.iter().filter(|s| s.is_some()).count() != 1 {
+            bail!(
+                "access rule '{}' needs exactly one of `zone`, `zone_id` or `account_id`",
+                self.notes
+            );
+        }
+        if self.notes.is_empty() {
+            bail!("access rule `notes` must not be empty");
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for AccessRuleTarget {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "IP Access rule '{}'", self.notes)
+    }
+}
+
+/// Item of an account-level IP List, e.g. referenced by WAF custom rules.
+///
+/// The items managed by cf-ddns are identified by their `comment`; other items are left alone.
+/// IPv6 addresses are stored as their /64 prefix, as IP Lists do not accept single IPv6 addresses.
+#[derive(Deserialize, Debug, Clone)]
+pub struct IpListTarget {
+    pub account_id: String,
+    pub list_id: String,
+    #[serde(default = "default_marker")]
+    pub comment: String,
+}
+
+impl IpListTarget {
+    fn validate(&self) -> Result<()> {
+        if self.comment.is_empty() {
+            bail!("IP List item `comment` must not be empty");
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for IpListTarget {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "IP List item '{}'", self.comment)
+    }
+}
+
+/// Zero Trust Gateway DNS location identified by the source address of its queries.
+///
+/// The location networks of each enabled address family are replaced with the network of the
+/// current address: the /32 for IPv4, and the prefix of `ipv6_prefix_len` bits for IPv6.
+#[derive(Deserialize, Debug, Clone)]
+pub struct GatewayLocationTarget {
+    pub account_id: String,
+    pub location_id: String,
+    #[serde(default = "default_ipv6_prefix_len")]
+    pub ipv6_prefix_len: u8,
+}
+
+const fn default_ipv6_prefix_len() -> u8 {
+    64
+}
+
+impl GatewayLocationTarget {
+    fn validate(&self) -> Result<()> {
+        if !(1..=128).contains(&self.ipv6_prefix_len) {
+            bail!(
+                "Gateway location `ipv6_prefix_len` must be between 1 and 128, got {}",
+                self.ipv6_prefix_len
+            );
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for GatewayLocationTarget {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Gateway location '{}'", self.location_id)
+    }
+}
+
+/// Origin of a Load Balancer pool, identified by its name within the pool.
+#[derive(Deserialize, Debug, Clone)]
+pub struct LoadBalancerOriginTarget {
+    pub account_id: String,
+    pub pool_id: String,
+    pub origin: String,
+}
+
+impl fmt::Display for LoadBalancerOriginTarget {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Load Balancer origin '{}'", self.origin)
+    }
+}
+
+/// Policy of a Zero Trust Access application, allowing the current address with an `ip` include
+/// rule.
+///
+/// Only the rule cf-ddns added is replaced when the address changes; the other include, require
+/// and exclude rules are left untouched.
+#[derive(Deserialize, Debug, Clone)]
+#[expect(
+    clippy::struct_field_names,
+    reason = "named after the configuration keys"
+)]
+pub struct AccessPolicyTarget {
+    pub account_id: String,
+    pub app_id: String,
+    pub policy_id: String,
+}
+
+impl fmt::Display for AccessPolicyTarget {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Access policy '{}'", self.policy_id)
+    }
+}
+
+/// Workers KV value holding a JSON document with the current addresses, for Workers to read:
+/// `{"v4": "203.0.113.5", "v6": null, "timestamp": 1767225600, "hostname": "home.example.com"}`.
+///
+/// Both address families share the value; a family that is not enabled is always `null`.
+#[derive(Deserialize, Debug, Clone)]
+pub struct WorkersKvTarget {
+    pub account_id: String,
+    pub namespace_id: String,
+    pub key: String,
+    /// Published as-is in the `hostname` field.
+    pub hostname: String,
+}
+
+/// The longest key accepted by Workers KV, in bytes.
+const MAX_KV_KEY_LEN: usize = 512;
+
+impl WorkersKvTarget {
+    fn validate(&self) -> Result<()> {
+        if self.key.is_empty() || self.key.len() > MAX_KV_KEY_LEN {
+            bail!("Workers KV `key` must be between 1 and {MAX_KV_KEY_LEN} bytes long");
+        }
+        if self.key == "." || self.key == ".." {
+            bail!("Workers KV `key` cannot be '{}'", self.key);
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for WorkersKvTarget {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Workers KV value '{}'", self.key)
+    }
+}
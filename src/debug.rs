@@ -5,8 +5,12 @@ use std::net::IpAddr;
 use anyhow::{Context, Result};
 use clap::Subcommand;
 
+use crate::cli::Cli;
+use crate::cloudflare::CloudFlare;
+use crate::config::Config;
 use crate::getifaddrs::{AddressFlags, InterfaceFlags, getifaddrs};
 use crate::lookup::interface::{is_public_ipv4, is_public_ipv6};
+use crate::updater::preflight::{check_zone, configured_zones, zone_holding};
 
 #[derive(Debug, Subcommand, Clone)]
 pub enum DebugCommand {
@@ -15,12 +19,19 @@ pub enum DebugCommand {
     /// Shows link-level flags (UP, LOOPBACK) and per-address notes (temporary, deprecated,
     /// non-public). Useful for diagnosing why an interface or address is not being used.
     Interfaces,
+    /// Verify the configured Cloudflare credentials and zones.
+    ///
+    /// Reports the API token status and expiry, lists the zones visible to the credentials,
+    /// and checks that every configured zone is reachable with DNS edit permission. Records
+    /// configured without a `zone` show the zone inferred from their name.
+    Cloudflare,
 }
 
 impl DebugCommand {
-    pub fn run(&self) -> Result<()> {
+    pub async fn run(&self, cli: &Cli) -> Result<()> {
         match self {
             DebugCommand::Interfaces => print_interfaces(),
+            DebugCommand::Cloudflare => print_cloudflare(cli).await,
         }
     }
 }
@@ -65,3 +76,75 @@ fn print_interfaces() -> Result<()> {
 
     Ok(())
 }
+
+async fn print_cloudflare(cli: &Cli) -> Result<()> {
+    let config = Config::load(cli.config.as_deref())?;
+    let cf = CloudFlare::new(&config.auth, &config.api)?;
+
+    if cf.uses_token() {
+        match cf.verify_token().await {
+            Ok(token) => {
+                let expiry = token.expires_on.as_deref().unwrap_or("never");
+                println!("Token {}: {} (expires: {expiry})", token.id, token.status);
+            }
+            Err(e) => println!("Token: verification failed: {e:#}"),
+        }
+    } else {
+        println!("Token: not verified, using Global API Key");
+    }
+    println!();
+
+    match cf.list_zones(None).await {
+        Ok(zones) => {
+            println!("Visible zones ({}):", zones.len());
+            for zone in &zones {
+                println!("  {:<40}{}", zone.name, zone.id);
+            }
+        }
+        Err(e) => println!("Visible zones: unable to list zones: {e:#}"),
+    }
+    println!();
+
+    println!("Configured zones:");
+    for configured in configured_zones(&config.records) {
+        let zone = configured.zone;
+        let check = match configured.auth {
+            Some(auth) if *auth != config.auth => {
+                let cf = CloudFlare::new(auth, &config.api)?;
+                check_zone(&cf, zone, configured.zone_id).await
+            }
+            _ => check_zone(&cf, zone, configured.zone_id).await,
+        };
+        let result = match check {
+            Ok(found) if found.can_edit_dns().is_some() => "OK (DNS edit)".to_owned(),
+            Ok(_) => "OK (permissions not reported)".to_owned(),
+            Err(e) => format!("FAILED: {e:#}"),
+        };
+        println!("  {zone:<40}{result}");
+    }
+
+    let mut inferred = Vec::new();
+    for rec in config.records.v4.iter().chain(&config.records.v6) {
+        if rec.zone.is_none() && rec.zone_id.is_none() && !inferred.contains(&&rec.name) {
+            inferred.push(&rec.name);
+            if inferred.len() == 1 {
+                println!();
+                println!("Inferred zones:");
+            }
+            let found = match rec.auth.as_ref() {
+                Some(auth) if *auth != config.auth => {
+                    let cf = CloudFlare::new(auth, &config.api)?;
+                    zone_holding(&cf, &rec.name).await
+                }
+                _ => zone_holding(&cf, &rec.name).await,
+            };
+            let result = match found {
+                Ok(zone) => zone.name,
+                Err(e) => format!("FAILED: {e:#}"),
+            };
+            println!("  {:<40}{result}", rec.name);
+        }
+    }
+
+    Ok(())
+}
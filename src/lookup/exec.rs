@@ -1,16 +1,18 @@
 use std::net::{Ipv4Addr, Ipv6Addr};
+use std::str::FromStr;
 
-use anyhow::{Context, Result, bail};
+use anyhow::{Context, Result, anyhow, bail, ensure};
 
 use crate::lookup::LookupSpec;
 
 pub struct ExecLookup {
     cmd: String,
+    all: bool,
 }
 
 impl ExecLookup {
-    pub const fn new(cmd: String) -> Self {
-        Self { cmd }
+    pub const fn new(cmd: String, all: bool) -> Self {
+        Self { cmd, all }
     }
 
     async fn run(&self) -> Result<String> {
@@ -40,6 +42,35 @@ impl LookupSpec for ExecLookup {
         out.parse()
             .with_context(|| format!("failed to parse IPv6 address from command output: {out:?}"))
     }
+
+    async fn lookup_all_v4(&self) -> Result<Vec<Ipv4Addr>> {
+        if !self.all {
+            return Ok(vec![self.lookup_v4().await?]);
+        }
+        parse_all(&self.run().await?, "IPv4")
+    }
+
+    async fn lookup_all_v6(&self) -> Result<Vec<Ipv6Addr>> {
+        if !self.all {
+            return Ok(vec![self.lookup_v6().await?]);
+        }
+        parse_all(&self.run().await?, "IPv6")
+    }
+}
+
+/// Parses every whitespace-separated address in `out`, skipping repeated ones.
+fn parse_all<T: FromStr + PartialEq>(out: &str, family: &str) -> Result<Vec<T>> {
+    let mut addrs = Vec::new();
+    for word in out.split_whitespace() {
+        let addr = word.parse().map_err(|_| {
+            anyhow!("failed to parse {family} address from command output: {word:?}")
+        })?;
+        if !addrs.contains(&addr) {
+            addrs.push(addr);
+        }
+    }
+    ensure!(!addrs.is_empty(), "command printed no {family} address");
+    Ok(addrs)
 }
 
 /// Builds a [`tokio::process::Command`] that runs `cmd` through the system shell.
@@ -27,10 +27,11 @@ impl fmt::Display for IpFamily {
 pub struct InterfaceLookup {
     interface: String,
     matchers: MatcherConfig,
+    all: bool,
 }
 
 impl InterfaceLookup {
-    pub fn new(interface: String, matchers: MatcherConfig) -> Result<Self> {
+    pub fn new(interface: String, matchers: MatcherConfig, all: bool) -> Result<Self> {
         let interface = interface.trim().to_owned();
         ensure!(
             !interface.is_empty(),
@@ -39,15 +40,18 @@ impl InterfaceLookup {
         Ok(Self {
             interface,
             matchers,
+            all,
         })
     }
 
+    /// The matching addresses of the interface, at least one. Only the first one unless `all`
+    /// is set.
     fn lookup_ip<T>(
         &self,
         family: IpFamily,
         extract: impl Fn(IpAddr) -> Option<T>,
         filter: impl Fn(T) -> bool,
-    ) -> Result<T>
+    ) -> Result<Vec<T>>
     where
         T: Copy + fmt::Display + Eq,
     {
@@ -84,8 +88,14 @@ impl InterfaceLookup {
 
         ensure!(found_interface, "interface `{}` not found", self.interface);
 
-        if let Some(addr) = candidates.iter().copied().find(|a| filter(*a)) {
-            return Ok(addr);
+        let matching: Vec<T> = candidates
+            .iter()
+            .copied()
+            .filter(|a| filter(*a))
+            .take(if self.all { usize::MAX } else { 1 })
+            .collect();
+        if !matching.is_empty() {
+            return Ok(matching);
         }
 
         if candidates.is_empty() {
@@ -109,8 +119,8 @@ impl InterfaceLookup {
     }
 }
 
-impl LookupSpec for InterfaceLookup {
-    async fn lookup_v4(&self) -> Result<Ipv4Addr> {
+impl InterfaceLookup {
+    fn lookup_all_ipv4(&self) -> Result<Vec<Ipv4Addr>> {
         self.lookup_ip(
             IpFamily::V4,
             |ip| match ip {
@@ -126,7 +136,7 @@ impl LookupSpec for InterfaceLookup {
         )
     }
 
-    async fn lookup_v6(&self) -> Result<Ipv6Addr> {
+    fn lookup_all_ipv6(&self) -> Result<Vec<Ipv6Addr>> {
         self.lookup_ip(
             IpFamily::V6,
             |ip| match ip {
@@ -143,6 +153,24 @@ impl LookupSpec for InterfaceLookup {
     }
 }
 
+impl LookupSpec for InterfaceLookup {
+    async fn lookup_v4(&self) -> Result<Ipv4Addr> {
+        Ok(self.lookup_all_ipv4()?[0])
+    }
+
+    async fn lookup_v6(&self) -> Result<Ipv6Addr> {
+        Ok(self.lookup_all_ipv6()?[0])
+    }
+
+    async fn lookup_all_v4(&self) -> Result<Vec<Ipv4Addr>> {
+        self.lookup_all_ipv4()
+    }
+
+    async fn lookup_all_v6(&self) -> Result<Vec<Ipv6Addr>> {
+        self.lookup_all_ipv6()
+    }
+}
+
 pub(crate) fn is_public_ipv4(addr: Ipv4Addr) -> bool {
     is_global_ipv4(addr) && !addr.is_multicast() && addr.octets()[0..3] != [192, 88, 99]
 }
@@ -157,7 +185,7 @@ mod tests {
 
     #[test]
     fn empty_interface_name_errors() {
-        assert!(InterfaceLookup::new("   ".to_owned(), MatcherConfig::default()).is_err());
+        assert!(InterfaceLookup::new("   ".to_owned(), MatcherConfig::default(), false).is_err());
     }
 
     // --- is_public_ipv4 ---
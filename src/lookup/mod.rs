@@ -12,6 +12,15 @@ pub use interface::InterfaceLookup;
 pub trait LookupSpec {
     async fn lookup_v4(&self) -> Result<Ipv4Addr>;
     async fn lookup_v6(&self) -> Result<Ipv6Addr>;
+
+    /// Every address to publish, at least one. A single address unless the provider is
+    /// configured to return them all.
+    async fn lookup_all_v4(&self) -> Result<Vec<Ipv4Addr>> {
+        Ok(vec![self.lookup_v4().await?])
+    }
+    async fn lookup_all_v6(&self) -> Result<Vec<Ipv6Addr>> {
+        Ok(vec![self.lookup_v6().await?])
+    }
 }
 
 /// Lookup provider for a single protocol.
@@ -36,4 +45,18 @@ impl LookupSpec for Provider {
             Provider::Interface(i) => i.lookup_v6().await,
         }
     }
+    async fn lookup_all_v4(&self) -> Result<Vec<Ipv4Addr>> {
+        match self {
+            Provider::ICanHazIp(i) => i.lookup_all_v4().await,
+            Provider::Exec(e) => e.lookup_all_v4().await,
+            Provider::Interface(i) => i.lookup_all_v4().await,
+        }
+    }
+    async fn lookup_all_v6(&self) -> Result<Vec<Ipv6Addr>> {
+        match self {
+            Provider::ICanHazIp(i) => i.lookup_all_v6().await,
+            Provider::Exec(e) => e.lookup_all_v6().await,
+            Provider::Interface(i) => i.lookup_all_v6().await,
+        }
+    }
 }
@@ -47,9 +47,10 @@ async fn main() -> Result<()> {
 
     let cli: Cli = Cli::parse();
 
-    // Debug commands don't require config or tracing setup; handle them first for clean output.
+    // Debug commands load config on their own and skip tracing setup; handle them first for
+    // clean output.
     if let Some(cli::Command::Debug(ref debug_cmd)) = cli.command {
-        debug_cmd.run()?;
+        debug_cmd.run(&cli).await?;
         return Ok(());
     }
 
@@ -13,12 +13,15 @@ impl AppContext {
         Fut: Future,
     {
         let updater = self.new_updater()?;
+        updater.preflight(&self.config.records).await?;
         let mut interval = interval(self.config.interval);
         interval.set_missed_tick_behavior(MissedTickBehavior::Skip);
         IntervalStream::new(interval)
             .take_until(cancel)
             .fold(updater, |updater, _| async move {
-                updater.update(&self.config.records).await;
+                updater
+                    .update(&self.config.records, &self.config.targets)
+                    .await;
                 updater
             })
             .await;
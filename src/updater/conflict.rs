@@ -0,0 +1,22 @@
+//! Existing resources cf-ddns refuses to change on its own
+
+use std::fmt;
+
+/// An existing resource is not in a state cf-ddns can update safely, e.g. an SPF policy with a
+/// mechanism of unknown ownership. Retrying cannot help until it is fixed by hand.
+#[derive(Debug)]
+pub struct Conflict(pub String);
+
+impl fmt::Display for Conflict {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl std::error::Error for Conflict {}
+
+/// Returns `true` if `err` was caused by a [`Conflict`].
+pub fn is_conflict(err: &anyhow::Error) -> bool {
+    err.chain()
+        .any(|cause| cause.downcast_ref::<Conflict>().is_some())
+}
@@ -0,0 +1,123 @@
+//! Heartbeat TXT record, for checking liveness with plain `dig`
+
+use std::fmt::Write;
+use std::rc::Rc;
+use std::time::{Instant, SystemTime};
+
+use anyhow::{Context, Result};
+use tracing::{info, warn};
+
+use crate::cloudflare::record::{DnsContent, txt_content, txt_text};
+use crate::config::{HeartbeatConfig, RecordSettings};
+use crate::updater::{Updater, join_addrs, ownership};
+use crate::util::time::rfc3339;
+
+/// Leads the content of the heartbeat record, to tell it apart from other TXT records at its name.
+const PREFIX: &str = "cf-ddns ";
+
+/// The last heartbeat written.
+#[derive(Debug)]
+pub(super) struct LastHeartbeat {
+    rec_id: Rc<str>,
+    addresses: String,
+    written: Instant,
+}
+
+impl Updater {
+    /// Publishes the heartbeat after a successful update, unless it was written less than
+    /// `min_interval` ago with the same addresses.
+    pub(super) async fn heartbeat(&self) {
+        let Some(config) = &self.heartbeat else {
+            return;
+        };
+        let addresses = self.current_addresses();
+        let recent = self.last_heartbeat.borrow().as_ref().is_some_and(|last| {
+            last.addresses == addresses && last.written.elapsed() < config.min_interval
+        });
+        if recent {
+            return;
+        }
+
+        match self.write_heartbeat(config, &addresses).await {
+            Ok(rec_id) => {
+                info!("Updated heartbeat record '{}'", config.name);
+                *self.last_heartbeat.borrow_mut() = Some(LastHeartbeat {
+                    rec_id,
+                    addresses,
+                    written: Instant::now(),
+                });
+            }
+            Err(e) => {
+                // the record may have been deleted, look it up again next time
+                self.last_heartbeat.borrow_mut().take();
+                warn!("Failed to update heartbeat record '{}': {e:#}", config.name);
+            }
+        }
+    }
+
+    /// The addresses last looked up by the global providers, e.g. ` v4=203.0.113.5`,
+    /// with the addresses of a set separated by commas. Records with their own provider may
+    /// have been updated to other addresses, which are not listed.
+    fn current_addresses(&self) -> String {
+        let cache = self.lookup_cache.borrow();
+        let mut addresses = String::new();
+        if let Some(v4) = cache.get_v4(&self.lookup_config.v4) {
+            let _ = write!(addresses, " v4={}", join_addrs(v4, ","));
+        }
+        if let Some(v6) = cache.get_v6(&self.lookup_config.v6) {
+            let _ = write!(addresses, " v6={}", join_addrs(v6, ","));
+        }
+        addresses
+    }
+
+    async fn write_heartbeat(&self, config: &HeartbeatConfig, addresses: &str) -> Result<Rc<str>> {
+        let account = self.account(config.auth.as_ref());
+        let content = DnsContent::TXT {
+            content: txt_content(&format!(
+                "{PREFIX}version={} updated={}{addresses}",
+                env!("CARGO_PKG_VERSION"),
+                rfc3339(SystemTime::now())
+            )),
+        };
+        let zone_id = self
+            .configured_zone_id(account, &config.zone, config.zone_id.as_deref())
+            .await?;
+
+        let last_id = self
+            .last_heartbeat
+            .borrow()
+            .as_ref()
+            .map(|last| Rc::clone(&last.rec_id));
+        let rec_id = match last_id {
+            Some(id) => Some(id),
+            None => account
+                .cf
+                .list_records_of_type(&zone_id, &config.name, "TXT")
+                .await
+                .context("Failed to list the records")?
+                .into_iter()
+                .find(|record| match &record.content {
+                    DnsContent::TXT { content } => txt_text(content).starts_with(PREFIX),
+                    _ => false,
+                })
+                .map(|record| Rc::from(record.id)),
+        };
+
+        let record = if let Some(id) = rec_id {
+            account
+                .cf
+                .update_record_content(&zone_id, &id, &config.name, content)
+                .await
+                .context("Failed to update the record")?
+        } else {
+            let settings = RecordSettings::default();
+            let settings = ownership::stamp(self.ownership.as_ref(), &settings);
+            account
+                .cf
+                .create_record_content(&zone_id, &config.name, content, &settings)
+                .await
+                .context("Failed to create the record")?
+        };
+        Ok(Rc::from(record.id))
+    }
+}
@@ -0,0 +1,71 @@
+//! Address hints of HTTPS and SVCB records
+
+use std::net::IpAddr;
+
+use anyhow::{Context, Result, bail};
+
+use crate::cloudflare::record::{DnsContent, SvcbData};
+use crate::config::ZoneRecord;
+use crate::updater::conflict::Conflict;
+use crate::updater::{Updater, ownership};
+use crate::util::svcb::set_param;
+
+impl Updater {
+    /// Sets the `ipv4hint` or `ipv6hint` of every `record_type` (HTTPS or SVCB) record at
+    /// `rec.name` to `addr`, preserving the priority, target and other parameters. The records
+    /// are never created, as only their owner knows what they should point to.
+    pub(super) async fn update_hints(
+        &self,
+        rec: &ZoneRecord,
+        record_type: &str,
+        addr: IpAddr,
+    ) -> Result<()> {
+        let key = if addr.is_ipv4() {
+            "ipv4hint"
+        } else {
+            "ipv6hint"
+        };
+        let account = self.account(rec.auth.as_ref());
+        let zone_id = self.record_zone_id(account, rec).await?;
+
+        // IPv4 and IPv6 hints live in the same parameters, which must be read again after the
+        // other family changed them.
+        let _guard = self.shared_writes.lock().await;
+        // Not cached, as the current parameters are needed anyway to preserve them.
+        let records = account
+            .cf
+            .list_records_of_type(&zone_id, &rec.name, record_type)
+            .await
+            .context("Failed to list the records")?;
+        let owned = ownership::rewritable(self.ownership.as_ref(), rec.adopt, &records)?;
+        if owned.is_empty() {
+            bail!(Conflict(
+                "Cannot find the record, it must be created first".to_owned()
+            ));
+        }
+
+        for record in owned {
+            let Some(data) = record.content.svcb_data() else {
+                continue;
+            };
+            let value = set_param(&data.value, key, &addr.to_string());
+            if value == data.value {
+                continue;
+            }
+            let data = SvcbData {
+                value,
+                ..data.clone()
+            };
+            let content = match record.content {
+                DnsContent::SVCB { .. } => DnsContent::SVCB { data },
+                _ => DnsContent::HTTPS { data },
+            };
+            account
+                .cf
+                .update_record_content(&zone_id, &record.id, &rec.name, content)
+                .await
+                .context("Failed to update the record")?;
+        }
+        Ok(())
+    }
+}
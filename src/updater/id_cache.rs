@@ -3,23 +3,72 @@ use std::fs::File;
 use std::net::IpAddr;
 use std::path::{Path, PathBuf};
 use std::rc::Rc;
+use std::time::{Duration, Instant};
 
 use anyhow::{Context, Result};
 use serde::{Deserialize, Serialize};
 
-use crate::cloudflare::record::DnsContent::{A, AAAA};
+use crate::cloudflare::record::DnsContent::{A, AAAA, HTTPS, PTR, SVCB, TXT};
 use crate::cloudflare::record::DnsRecord;
+use crate::config::{Auth, OwnershipMarker};
 
 #[derive(Debug, Default, Serialize, Deserialize)]
 pub struct IdCache {
     #[serde(skip)]
     pub(crate) path: PathBuf,
+    /// IDs seen with the default credentials. Kept at the top level for compatibility
+    /// with caches written by older versions.
+    #[serde(flatten)]
+    default: AccountIds,
+    /// IDs seen with per-zone or per-record credentials, keyed by credential fingerprint,
+    /// so IDs from different accounts never mix.
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    accounts: HashMap<String, AccountIds>,
+    /// Values last written to targets whose managed part cannot be told apart otherwise,
+    /// keyed by target and address family.
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    targets: HashMap<String, String>,
+    /// Ownership marker the record identifiers were checked against, as `tag:…` or `comment:…`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    ownership: Option<String>,
+}
+
+/// Cached IDs of a single Cloudflare account.
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AccountIds {
     /// Zone name to Zone Id
     zones: HashMap<String, Rc<str>>,
     /// Record name to Record Ids (v4, v6)
     records: HashMap<String, RecordIdCache>,
+    /// Zone names that Cloudflare reported as unknown, and when they were looked up.
+    /// Kept in memory only so a restart always retries.
+    #[serde(skip)]
+    missing_zones: HashMap<String, Instant>,
+    /// Record name to the zone inferred for it, for records configured without a `zone`.
+    /// Kept in memory only so a restart picks up zones added since.
+    #[serde(skip)]
+    record_zones: HashMap<String, Rc<str>>,
+}
+
+/// Cache scope for `auth`: a fingerprint of the credentials, so the secret itself never ends up
+/// in the cache file. Uses 64-bit FNV-1a, which is stable across builds and platforms.
+pub fn scope_for(auth: &Auth) -> String {
+    let input = match auth {
+        Auth::Token { token } => format!("token:{token}"),
+        Auth::GlobalKey { email, key } => format!("key:{email}:{key}"),
+    };
+    let hash = input.bytes().fold(0xcbf2_9ce4_8422_2325_u64, |hash, byte| {
+        (hash ^ u64::from(byte)).wrapping_mul(0x0100_0000_01b3)
+    });
+    format!("{hash:016x}")
 }
 
+/// How long an unknown zone name is remembered before it is looked up again.
+const MISSING_ZONE_TTL: Duration = Duration::from_hours(1);
+
+/// Identifiers of the A and AAAA record of a name. Records published as a set are listed on
+/// every change instead, as a name then has several records per address family.
 #[derive(Debug, Default, Clone, Serialize, Deserialize)]
 pub struct RecordIdCache {
     pub v4: Option<Rc<str>>,
@@ -38,6 +87,19 @@ impl RecordIdCache {
         match &record.content {
             A { .. } => self.v4 = Some(record.id.clone().into()),
             AAAA { .. } => self.v6 = Some(record.id.clone().into()),
+            // not looked up by address
+            HTTPS { .. } | SVCB { .. } | TXT { .. } | PTR { .. } => {}
+        }
+    }
+
+    pub fn remove(&mut self, record: &DnsRecord) {
+        let id = match &record.content {
+            A { .. } => &mut self.v4,
+            AAAA { .. } => &mut self.v6,
+            HTTPS { .. } | SVCB { .. } | TXT { .. } | PTR { .. } => return,
+        };
+        if id.as_deref() == Some(record.id.as_str()) {
+            *id = None;
         }
     }
 }
@@ -58,26 +120,107 @@ impl IdCache {
 }
 
 impl IdCache {
-    pub fn get_zone(&self, zone: &str) -> Option<Rc<str>> {
-        self.zones.get(zone).cloned()
+    fn account(&self, scope: Option<&str>) -> Option<&AccountIds> {
+        match scope {
+            None => Some(&self.default),
+            Some(scope) => self.accounts.get(scope),
+        }
+    }
+
+    fn account_mut(&mut self, scope: Option<&str>) -> &mut AccountIds {
+        match scope {
+            None => &mut self.default,
+            Some(scope) => self.accounts.entry(scope.to_owned()).or_default(),
+        }
+    }
+
+    /// Drops the record identifiers unless they were looked up with `marker`, as cached
+    /// identifiers are used without checking the marker again.
+    pub fn set_ownership(&mut self, marker: Option<&OwnershipMarker>) {
+        let marker = marker.map(|marker| match marker {
+            OwnershipMarker::Tag(tag) => format!("tag:{tag}"),
+            OwnershipMarker::Comment(comment) => format!("comment:{comment}"),
+        });
+        if self.ownership != marker {
+            self.default.records.clear();
+            for account in self.accounts.values_mut() {
+                account.records.clear();
+            }
+            self.ownership = marker;
+        }
+    }
+
+    pub fn get_zone(&self, scope: Option<&str>, zone: &str) -> Option<Rc<str>> {
+        self.account(scope)?.zones.get(zone).cloned()
+    }
+
+    pub fn get_record(&self, scope: Option<&str>, name: &str, addr: &IpAddr) -> Option<Rc<str>> {
+        self.account(scope)?
+            .records
+            .get(name)
+            .and_then(|r| r.get_for(addr))
+    }
+
+    pub fn save_zone(&mut self, scope: Option<&str>, zone: String, id: String) {
+        let account = self.account_mut(scope);
+        account.missing_zones.remove(&zone);
+        account.zones.insert(zone, id.into());
+    }
+
+    pub fn is_zone_missing(&self, scope: Option<&str>, zone: &str) -> bool {
+        self.account(scope)
+            .and_then(|account| account.missing_zones.get(zone))
+            .is_some_and(|since| since.elapsed() < MISSING_ZONE_TTL)
+    }
+
+    pub fn mark_zone_missing(&mut self, scope: Option<&str>, zone: &str) {
+        self.account_mut(scope)
+            .missing_zones
+            .insert(zone.to_owned(), Instant::now());
+    }
+
+    pub fn get_record_zone(&self, scope: Option<&str>, name: &str) -> Option<Rc<str>> {
+        self.account(scope)?.record_zones.get(name).cloned()
     }
 
-    pub fn get_record(&self, name: &str, addr: &IpAddr) -> Option<Rc<str>> {
-        self.records.get(name).and_then(|r| r.get_for(addr))
+    pub fn save_record_zone(&mut self, scope: Option<&str>, name: &str, zone: Rc<str>) {
+        self.account_mut(scope)
+            .record_zones
+            .insert(name.to_owned(), zone);
     }
 
-    pub fn save_zone(&mut self, zone: String, id: String) {
-        self.zones.insert(zone, id.into());
+    pub fn get_target_value(&self, key: &str) -> Option<&str> {
+        self.targets.get(key).map(String::as_str)
     }
 
-    pub fn update_record(&mut self, name: &str, record: &DnsRecord) {
-        self.records
+    pub fn save_target_value(&mut self, key: String, value: String) {
+        self.targets.insert(key, value);
+    }
+
+    pub fn remove_target_value(&mut self, key: &str) {
+        self.targets.remove(key);
+    }
+
+    pub fn update_record(&mut self, scope: Option<&str>, name: &str, record: &DnsRecord) {
+        self.account_mut(scope)
+            .records
             // it is most likely that the record is not present
             // in the cache when this function is called
             .entry(name.to_owned())
             .or_default()
             .update(record);
     }
+
+    /// Replaces the identifiers cached for `name`, e.g. with the ones of a fresh listing.
+    pub fn save_records(&mut self, scope: Option<&str>, name: &str, ids: RecordIdCache) {
+        self.account_mut(scope).records.insert(name.to_owned(), ids);
+    }
+
+    pub fn remove_record(&mut self, scope: Option<&str>, name: &str, record: &DnsRecord) {
+        if let Some(ids) = self.account_mut(scope).records.get_mut(name) {
+            ids.remove(record);
+        }
+    }
 }
 
 #[cfg(test)]
@@ -93,7 +236,12 @@ mod tests {
             path: temp_file.clone(),
             ..Default::default()
         };
-        cache.save_zone("example.com".to_owned(), "zone_id_123".to_owned());
+        cache.save_zone(None, "example.com".to_owned(), "zone_id_123".to_owned());
+        cache.save_zone(
+            Some("other"),
+            "example.com".to_owned(),
+            "zone_id_456".to_owned(),
+        );
         cache.save()?;
 
         assert!(temp_file.exists());
@@ -101,11 +249,63 @@ mod tests {
         let loaded = IdCache::load(&temp_file)?;
         assert_eq!(loaded.path, temp_file);
         assert_eq!(
-            loaded.get_zone("example.com").as_deref(),
+            loaded.get_zone(None, "example.com").as_deref(),
             Some("zone_id_123")
         );
+        assert_eq!(
+            loaded.get_zone(Some("other"), "example.com").as_deref(),
+            Some("zone_id_456")
+        );
+        assert_eq!(loaded.get_zone(Some("unknown"), "example.com"), None);
 
         let _ = std::fs::remove_file(temp_file);
         Ok(())
     }
+
+    #[test]
+    fn scope_is_stable_and_hides_the_secret() {
+        let auth = Auth::Token {
+            token: "secret-token".to_owned(),
+        };
+        let scope = scope_for(&auth);
+        assert_eq!(scope, scope_for(&auth));
+        assert_eq!(scope.len(), 16);
+        assert!(!scope.contains("secret"));
+        let other = Auth::Token {
+            token: "other-token".to_owned(),
+        };
+        assert_ne!(scope, scope_for(&other));
+    }
+
+    #[test]
+    fn legacy_layout_loads_as_default_account() -> Result<()> {
+        let cache: IdCache = serde_json::from_str(
+            r#"{"zones":{"example.com":"zone_id_123"},"records":{"a.example.com":{"v4":"rec1","v6":null}}}"#,
+        )?;
+        assert_eq!(
+            cache.get_zone(None, "example.com").as_deref(),
+            Some("zone_id_123")
+        );
+        assert_eq!(
+            cache
+                .get_record(None, "a.example.com", &IpAddr::from([192, 0, 2, 1]))
+                .as_deref(),
+            Some("rec1")
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn missing_zone_is_remembered_until_found() {
+        let mut cache = IdCache::default();
+        assert!(!cache.is_zone_missing(None, "example.com"));
+
+        cache.mark_zone_missing(None, "example.com");
+        assert!(cache.is_zone_missing(None, "example.com"));
+        assert!(!cache.is_zone_missing(None, "example.net"));
+        assert!(!cache.is_zone_missing(Some("other"), "example.com"));
+
+        cache.save_zone(None, "example.com".to_owned(), "zone_id_123".to_owned());
+        assert!(!cache.is_zone_missing(None, "example.com"));
+    }
 }
@@ -3,10 +3,11 @@ use std::net::{Ipv4Addr, Ipv6Addr};
 
 use crate::config::ProviderConfig;
 
+/// The addresses last looked up by each provider, sorted, as providers may return a set.
 #[derive(Debug, Default)]
 pub struct LookupCache {
-    v4: HashMap<ProviderConfig, Ipv4Addr>,
-    v6: HashMap<ProviderConfig, Ipv6Addr>,
+    v4: HashMap<ProviderConfig, Vec<Ipv4Addr>>,
+    v6: HashMap<ProviderConfig, Vec<Ipv6Addr>>,
 }
 
 pub enum UpdateResult<T> {
@@ -16,27 +17,81 @@ pub enum UpdateResult<T> {
 }
 
 impl LookupCache {
-    pub fn update_v4(&mut self, key: &ProviderConfig, v4: Ipv4Addr) -> UpdateResult<Ipv4Addr> {
-        if let Some(entry) = self.v4.get_mut(key) {
-            if *entry == v4 {
-                return UpdateResult::Unchanged;
-            }
-            let old = std::mem::replace(entry, v4);
-            return UpdateResult::Updated(old);
-        }
-        self.v4.insert(key.clone(), v4);
-        UpdateResult::Initialized
+    pub fn get_v4(&self, key: &ProviderConfig) -> Option<&[Ipv4Addr]> {
+        self.v4.get(key).map(Vec::as_slice)
+    }
+
+    pub fn get_v6(&self, key: &ProviderConfig) -> Option<&[Ipv6Addr]> {
+        self.v6.get(key).map(Vec::as_slice)
+    }
+
+    /// Forgets the addresses of `key`, so that the next lookup counts as a change.
+    pub fn forget_v4(&mut self, key: &ProviderConfig) {
+        self.v4.remove(key);
+    }
+
+    pub fn forget_v6(&mut self, key: &ProviderConfig) {
+        self.v6.remove(key);
+    }
+
+    /// Records `v4` as the addresses of `key`, in any order.
+    pub fn update_v4(
+        &mut self,
+        key: &ProviderConfig,
+        v4: &[Ipv4Addr],
+    ) -> UpdateResult<Vec<Ipv4Addr>> {
+        update(&mut self.v4, key, v4)
+    }
+
+    pub fn update_v6(
+        &mut self,
+        key: &ProviderConfig,
+        v6: &[Ipv6Addr],
+    ) -> UpdateResult<Vec<Ipv6Addr>> {
+        update(&mut self.v6, key, v6)
     }
+}
 
-    pub fn update_v6(&mut self, key: &ProviderConfig, v6: Ipv6Addr) -> UpdateResult<Ipv6Addr> {
-        if let Some(entry) = self.v6.get_mut(key) {
-            if *entry == v6 {
-                return UpdateResult::Unchanged;
-            }
-            let old = std::mem::replace(entry, v6);
-            return UpdateResult::Updated(old);
+fn update<T: Copy + Ord>(
+    cache: &mut HashMap<ProviderConfig, Vec<T>>,
+    key: &ProviderConfig,
+    addrs: &[T],
+) -> UpdateResult<Vec<T>> {
+    let mut addrs = addrs.to_vec();
+    addrs.sort_unstable();
+    addrs.dedup();
+    if let Some(entry) = cache.get_mut(key) {
+        if *entry == addrs {
+            return UpdateResult::Unchanged;
         }
-        self.v6.insert(key.clone(), v6);
-        UpdateResult::Initialized
+        let old = std::mem::replace(entry, addrs);
+        return UpdateResult::Updated(old);
+    }
+    cache.insert(key.clone(), addrs);
+    UpdateResult::Initialized
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn address_sets_compare_regardless_of_order() -> anyhow::Result<()> {
+        let key = ProviderConfig::ICanHazIp;
+        let (a, b): (Ipv6Addr, Ipv6Addr) = ("2001:db8::1".parse()?, "2001:db8::2".parse()?);
+        let mut cache = LookupCache::default();
+        assert!(matches!(
+            cache.update_v6(&key, &[a, b]),
+            UpdateResult::Initialized
+        ));
+        assert!(matches!(
+            cache.update_v6(&key, &[b, a]),
+            UpdateResult::Unchanged
+        ));
+        assert!(matches!(
+            cache.update_v6(&key, &[b]),
+            UpdateResult::Updated(old) if old == [a, b]
+        ));
+        Ok(())
     }
 }
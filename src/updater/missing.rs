@@ -0,0 +1,203 @@
+//! `on_missing` policies, applied to records whose address family can no longer be looked up
+
+use std::collections::HashMap;
+
+use anyhow::{Context, Result};
+use futures::future::join_all;
+use tracing::{error, info, warn};
+
+use crate::config::{OnMissing, ProviderConfig, ZoneRecord};
+use crate::updater::{UpdateStatus, Updater, ownership};
+
+/// Per-record state of the `on_missing` policies, keyed by record name.
+#[derive(Debug, Default)]
+pub struct MissingCycles {
+    v4: HashMap<String, Missing>,
+    v6: HashMap<String, Missing>,
+}
+
+#[derive(Debug, Default)]
+struct Missing {
+    /// Consecutive update cycles without an address.
+    cycles: u32,
+    /// Whether the policy has been applied, so it is not applied again every cycle.
+    applied: bool,
+}
+
+impl MissingCycles {
+    const fn family(&mut self, v6: bool) -> &mut HashMap<String, Missing> {
+        if v6 { &mut self.v6 } else { &mut self.v4 }
+    }
+
+    /// Resets the state of `records`, whose address was looked up.
+    fn found(&mut self, records: &[&ZoneRecord], v6: bool) {
+        let family = self.family(v6);
+        for rec in records {
+            family.remove(&rec.name);
+        }
+    }
+
+    /// Counts a cycle without an address for `records`, returning the ones whose policy is due
+    /// along with their number of cycles.
+    fn missed<'a>(&mut self, records: &[&'a ZoneRecord], v6: bool) -> Vec<(&'a ZoneRecord, u32)> {
+        let family = self.family(v6);
+        records
+            .iter()
+            .filter_map(|rec| {
+                let after = rec.on_missing.after()?;
+                let state = family.entry(rec.name.clone()).or_default();
+                state.cycles = state.cycles.saturating_add(1);
+                (!state.applied && state.cycles >= after).then_some((*rec, state.cycles))
+            })
+            .collect()
+    }
+
+    /// Records whether the policy of `name` was applied; if not, it is due again next cycle.
+    fn set_applied(&mut self, name: &str, v6: bool, applied: bool) {
+        if let Some(state) = self.family(v6).get_mut(name) {
+            state.applied = applied;
+        }
+    }
+}
+
+impl Updater {
+    /// Resets the `on_missing` state of `records`, whose address was just looked up.
+    pub(super) fn address_found(&self, records: &[&ZoneRecord], v6: bool) {
+        self.missing.borrow_mut().found(records, v6);
+    }
+
+    /// Counts an update cycle in which the address of `records` could not be looked up, and
+    /// applies their `on_missing` policy once it is due.
+    pub(super) async fn address_missing(
+        &self,
+        cache_key: &ProviderConfig,
+        records: &[&ZoneRecord],
+        v6: bool,
+    ) {
+        let due = self.missing.borrow_mut().missed(records, v6);
+        if due.is_empty() {
+            return;
+        }
+        // Once the address is back, it must update the records even if it did not change.
+        if v6 {
+            self.lookup_cache.borrow_mut().forget_v6(cache_key);
+        } else {
+            self.lookup_cache.borrow_mut().forget_v4(cache_key);
+        }
+
+        let rec_type = if v6 { "AAAA" } else { "A" };
+        let applied = join_all(due.into_iter().map(|(rec, cycles)| async move {
+            let applied = match rec.on_missing {
+                OnMissing::Keep => unreachable!("`keep` is never due"),
+                OnMissing::Delete { .. } => {
+                    warn!(
+                        "No address for {cycles} update cycle(s), deleting {rec_type} record '{}'",
+                        rec.name
+                    );
+                    match self.delete_address_records(rec, v6).await {
+                        Ok(()) => true,
+                        Err(e) if ownership::is_unowned(&e) => {
+                            warn!("Skipping {rec_type} record '{}': {e:#}", rec.name);
+                            true
+                        }
+                        Err(e) => {
+                            error!("Failed to delete {rec_type} record '{}': {e:#}", rec.name);
+                            false
+                        }
+                    }
+                }
+                OnMissing::Fallback { address, .. } => {
+                    warn!(
+                        "No address for {cycles} update cycle(s), pointing {rec_type} record '{}' \
+                         at fallback {address}",
+                        rec.name
+                    );
+                    let status = if cache_key.returns_set() {
+                        self.update_record_set_print(rec, &[address]).await
+                    } else {
+                        self.update_records_print(&[rec], address).await[0]
+                    };
+                    status != UpdateStatus::Retry
+                }
+            };
+            (rec, applied)
+        }))
+        .await;
+
+        let mut missing = self.missing.borrow_mut();
+        for (rec, applied) in applied {
+            missing.set_applied(&rec.name, v6, applied);
+        }
+    }
+
+    /// Deletes the existing A or AAAA records of `rec` that cf-ddns may manage, along with the
+    /// PTR records written for them.
+    async fn delete_address_records(&self, rec: &ZoneRecord, v6: bool) -> Result<()> {
+        let rec_type = if v6 { "AAAA" } else { "A" };
+        let account = self.account(rec.auth.as_ref());
+        let zone_id = self.record_zone_id(account, rec).await?;
+        let records = account
+            .cf
+            .list_records_of_type(&zone_id, &rec.name, rec_type)
+            .await
+            .context("Failed to list the records")?;
+        for record in ownership::rewritable(self.ownership.as_ref(), rec.adopt, &records)? {
+            account.cf.delete_record(&zone_id, &record.id).await?;
+            info!("Deleted {rec_type} record '{}'", rec.name);
+            let mut cache = self.id_cache.borrow_mut();
+            cache.remove_record(account.scope, &rec.name, record);
+            cache.save()?;
+        }
+        self.delete_written_ptrs(rec, v6).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Config;
+
+    #[test]
+    fn policy_is_due_once_after_threshold() -> Result<()> {
+        let config = Config::from_toml(
+            r#"
+                token = "test"
+                [[records]]
+                name = "a.example.com"
+                zone = "example.com"
+                v6 = true
+                on_missing = { action = "delete", after = 2 }
+                [[records]]
+                name = "b.example.com"
+                zone = "example.com"
+                v6 = true
+            "#,
+        )?;
+        let records: Vec<&ZoneRecord> = config.records.v6.iter().collect();
+        let due = |missing: &mut MissingCycles| -> Vec<(String, u32)> {
+            missing
+                .missed(&records, true)
+                .into_iter()
+                .map(|(rec, cycles)| (rec.name.clone(), cycles))
+                .collect()
+        };
+        let mut missing = MissingCycles::default();
+
+        assert_eq!(due(&mut missing), []);
+        assert_eq!(due(&mut missing), [("a.example.com".to_owned(), 2)]);
+        // a failed attempt is due again
+        missing.set_applied("a.example.com", true, false);
+        assert_eq!(due(&mut missing), [("a.example.com".to_owned(), 3)]);
+        // applied only once
+        missing.set_applied("a.example.com", true, true);
+        assert_eq!(due(&mut missing), []);
+        // the other family is counted separately
+        assert!(missing.missed(&records, false).is_empty());
+
+        // a found address starts over
+        missing.found(&records, true);
+        assert_eq!(due(&mut missing), []);
+        assert_eq!(due(&mut missing), [("a.example.com".to_owned(), 2)]);
+        Ok(())
+    }
+}
@@ -1,26 +1,45 @@
-use std::cell::RefCell;
+use std::borrow::Cow;
+use std::cell::{Cell, RefCell};
 use std::collections::HashMap;
-use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+use std::fmt;
+use std::net::IpAddr;
 use std::rc::Rc;
 use std::time::Duration;
 
 use anyhow::Result;
-use anyhow::{Context, anyhow};
+use anyhow::{Context, anyhow, bail};
 use futures::future::join_all;
 use futures::join;
+use futures::lock::Mutex;
 use tracing::{error, info, warn};
 
 use crate::AppContext;
-use crate::cloudflare::CloudFlare;
+use crate::cloudflare::error;
 use crate::cloudflare::record::DnsRecord;
-use crate::config::{LookupConfig, ProviderConfig, Records, RetryConfig, ZoneRecord};
+use crate::cloudflare::{CloudFlare, RecordChange};
+use crate::config::{
+    Auth, Duplicates, HeartbeatConfig, LookupConfig, OwnershipMarker, ProviderConfig, RecordKind,
+    RecordSettings, Records, RetryConfig, Target, ZoneRecord,
+};
 
 use crate::lookup::{LookupSpec, Provider};
-use crate::updater::id_cache::IdCache;
+use crate::updater::heartbeat::LastHeartbeat;
+use crate::updater::id_cache::{IdCache, RecordIdCache, scope_for};
 use crate::updater::lookup_cache::{LookupCache, UpdateResult};
+use crate::updater::missing::MissingCycles;
 
+mod conflict;
+mod heartbeat;
+mod hints;
 mod id_cache;
 mod lookup_cache;
+mod missing;
+mod ownership;
+pub mod preflight;
+mod ptr;
+mod record_set;
+mod spf;
+mod target;
 
 pub struct Updater {
     lookup_config: LookupConfig,
@@ -28,6 +47,9 @@ pub struct Updater {
     /// per-record overrides live in the same map so both code paths are identical.
     providers: HashMap<ProviderConfig, Provider>,
     cf: CloudFlare,
+    /// Clients for per-zone and per-record tokens that differ from the global credentials,
+    /// with the ID cache scope of each.
+    overrides: HashMap<Auth, (CloudFlare, String)>,
     // SAFETY: RefCell is used to allow mutable access to the cache across async calls.
     // We ensure that any borrow of the cache won't be held across an await point,
     // so there won't be concurrent borrows and should not cause any panicking.
@@ -37,8 +59,36 @@ pub struct Updater {
     // memory barriers and atomic operations instead.
     id_cache: RefCell<IdCache>,
     lookup_cache: RefCell<LookupCache>,
+    missing: RefCell<MissingCycles>,
+    /// Set once the batch endpoint turns out to be unavailable, to stop trying it.
+    batch_unavailable: Cell<bool>,
+    /// Serializes the read-modify-write updates of resources holding both address families
+    /// (Workers KV values, HTTPS and SVCB records, SPF policies, Gateway locations), so that
+    /// concurrent IPv4 and IPv6 updates each see the change made by the other.
+    shared_writes: Mutex<()>,
     retry: RetryConfig,
     interval: Duration,
+    ownership: Option<OwnershipMarker>,
+    heartbeat: Option<HeartbeatConfig>,
+    last_heartbeat: RefCell<Option<LastHeartbeat>>,
+}
+
+/// The Cloudflare client used for a record, and the ID cache scope of its credentials.
+#[derive(Clone, Copy)]
+struct Account<'a> {
+    cf: &'a CloudFlare,
+    /// `None` for the global credentials.
+    scope: Option<&'a str>,
+}
+
+/// Outcome of a single record update attempt.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum UpdateStatus {
+    Done,
+    /// Failed with an error that may go away on its own (network, 5xx, rate limiting).
+    Retry,
+    /// Failed with an error that retrying cannot fix (invalid token, missing permission, ...).
+    Failed,
 }
 
 impl AppContext {
@@ -68,46 +118,79 @@ impl AppContext {
                 }
             }
         }
-        let cf = CloudFlare::new(&self.config.token)?;
-        let id_cache = RefCell::new(IdCache::load(&self.id_cache_path).unwrap_or_else(|e| {
+        let cf = CloudFlare::new(&self.config.auth, &self.config.api)?;
+        let mut overrides = HashMap::new();
+        let heartbeat_auth = self.config.heartbeat.as_ref().map(|h| &h.auth);
+        let records = self.config.records.v4.iter().chain(&self.config.records.v6);
+        for auth in records.map(|rec| &rec.auth).chain(heartbeat_auth) {
+            if let Some(auth) = auth
+                && *auth != self.config.auth
+                && !overrides.contains_key(auth)
+            {
+                let client = CloudFlare::new(auth, &self.config.api)?;
+                overrides.insert(auth.clone(), (client, scope_for(auth)));
+            }
+        }
+        let mut id_cache = IdCache::load(&self.id_cache_path).unwrap_or_else(|e| {
             warn!("Failed to load cache: {e}");
             let mut cache = IdCache::default();
             cache.path.clone_from(&self.id_cache_path);
             cache
-        }));
+        });
+        id_cache.set_ownership(self.config.ownership.as_ref());
+        let id_cache = RefCell::new(id_cache);
         let lookup_cache = RefCell::new(LookupCache::default());
         Ok(Updater {
             lookup_config,
             providers,
             cf,
+            overrides,
             id_cache,
             lookup_cache,
+            missing: RefCell::default(),
+            batch_unavailable: Cell::new(false),
+            shared_writes: Mutex::new(()),
             retry: self.config.retry,
             interval: self.config.interval,
+            ownership: self.config.ownership.clone(),
+            heartbeat: self.config.heartbeat.clone(),
+            last_heartbeat: RefCell::new(None),
         })
     }
 
     pub async fn update(&self, name: Option<&str>) -> Result<()> {
         let updater = self.new_updater()?;
-        match name {
-            Some(name) => {
-                let records = self.config.records.filter_name(name);
-                updater.update(&records).await;
-            }
-            None => updater.update(&self.config.records).await,
+        if let Some(name) = name {
+            // only the zones of the named records need to be reachable
+            let records = self.config.records.filter_name(name);
+            updater.preflight(&records).await?;
+            updater.update(&records, &[]).await;
+        } else {
+            updater.preflight(&self.config.records).await?;
+            updater
+                .update(&self.config.records, &self.config.targets)
+                .await;
         }
         Ok(())
     }
 }
 
 impl Updater {
-    pub async fn update(&self, records: &Records) {
-        join!(self.update_v4(&records.v4), self.update_v6(&records.v6));
+    pub async fn update(&self, records: &Records, targets: &[Target]) {
+        let (v4, v6) = join!(
+            self.update_v4(&records.v4, targets),
+            self.update_v6(&records.v6, targets)
+        );
+        if v4 && v6 {
+            self.heartbeat().await;
+        }
     }
 
-    async fn update_v4(&self, records: &[ZoneRecord]) {
-        if records.is_empty() {
-            return;
+    /// Returns whether every record and target is up to date.
+    async fn update_v4(&self, records: &[ZoneRecord], targets: &[Target]) -> bool {
+        let targets: Vec<&Target> = targets.iter().filter(|t| t.v4).collect();
+        if records.is_empty() && targets.is_empty() {
+            return true;
         }
 
         // Group records by their effective lookup config (per-record override or global default).
@@ -116,19 +199,30 @@ impl Updater {
             let cfg = rec.lookup.as_ref().unwrap_or(&self.lookup_config.v4);
             groups.entry(cfg).or_default().push(rec);
         }
+        // Targets always follow the global provider.
+        if !targets.is_empty() {
+            groups.entry(&self.lookup_config.v4).or_default();
+        }
 
         let futs: Vec<_> = groups
             .iter()
             .filter_map(|(cfg, recs)| {
+                let targets = if *cfg == &self.lookup_config.v4 {
+                    targets.as_slice()
+                } else {
+                    &[]
+                };
                 if let Some(provider) = self.providers.get(*cfg) {
-                    Some(self.update_v4_with_provider(provider, cfg, recs))
+                    Some(self.update_v4_with_provider(provider, cfg, recs, targets))
                 } else {
                     error!("Lookup provider unexpectedly missing for {cfg:?}");
                     None
                 }
             })
             .collect();
-        join_all(futs).await;
+        // a group without a provider could not be updated
+        let complete = futs.len() == groups.len();
+        join_all(futs).await.into_iter().all(|done| done) && complete
     }
 
     async fn update_v4_with_provider(
@@ -136,8 +230,11 @@ impl Updater {
         provider: &Provider,
         cache_key: &ProviderConfig,
         records: &[&ZoneRecord],
-    ) {
-        let mut staged: Option<Ipv4Addr> = None;
+        targets: &[&Target],
+    ) -> bool {
+        let mut staged: Option<Vec<IpAddr>> = None;
+        let mut pending: Vec<&ZoneRecord> = records.to_vec();
+        let mut pending_targets: Vec<&Target> = targets.to_vec();
         let mut attempt: u32 = 0;
 
         loop {
@@ -145,19 +242,21 @@ impl Updater {
 
             // Perform lookup only when we don't have a staged IP yet.
             if staged.is_none() {
-                match provider.lookup_v4().await {
-                    Ok(addr) => {
-                        match self.lookup_cache.borrow_mut().update_v4(cache_key, addr) {
-                            UpdateResult::Initialized => info!("Current IPv4: {addr}"),
+                match provider.lookup_all_v4().await {
+                    Ok(addrs) => {
+                        self.address_found(records, false);
+                        let current = join_addrs(&addrs, ", ");
+                        match self.lookup_cache.borrow_mut().update_v4(cache_key, &addrs) {
+                            UpdateResult::Initialized => info!("Current IPv4: {current}"),
                             UpdateResult::Updated(old) => {
-                                info!("Current IPv4: {addr} (was {old})");
+                                info!("Current IPv4: {current} (was {})", join_addrs(&old, ", "));
                             }
                             UpdateResult::Unchanged => {
-                                info!("Current IPv4: {addr} (unchanged, skipping update)");
-                                return;
+                                info!("Current IPv4: {current} (unchanged, skipping update)");
+                                return true;
                             }
                         }
-                        staged = Some(addr);
+                        staged = Some(addrs.into_iter().map(IpAddr::from).collect());
                     }
                     Err(e) => {
                         error!("Failed to lookup current IPv4 address: {e}");
@@ -166,16 +265,16 @@ impl Updater {
             }
 
             // DNS update with the staged IP (no re-lookup on retry).
-            if let Some(addr) = staged {
-                let results = join_all(
-                    records
-                        .iter()
-                        .map(|rec| self.update_record_print(rec, addr.into())),
-                )
-                .await;
+            if let Some(addrs) = &staged {
+                let (results, target_results) = join!(
+                    self.update_records_to(&pending, addrs, cache_key.returns_set()),
+                    self.update_targets(&pending_targets, addrs[0])
+                );
 
-                if results.iter().all(|&s| s) {
-                    return;
+                pending = retry_pending(pending, results);
+                pending_targets = retry_pending(pending_targets, target_results);
+                if pending.is_empty() && pending_targets.is_empty() {
+                    return true;
                 }
             }
 
@@ -195,14 +294,20 @@ impl Updater {
 
         if staged.is_some() {
             error!("IPv4 DNS update failed, giving up until next interval");
+            // The next cycle must retry the update even if the address did not change.
+            self.lookup_cache.borrow_mut().forget_v4(cache_key);
         } else {
             error!("IPv4 lookup failed, giving up until next interval");
+            self.address_missing(cache_key, records, false).await;
         }
+        false
     }
 
-    async fn update_v6(&self, records: &[ZoneRecord]) {
-        if records.is_empty() {
-            return;
+    /// Returns whether every record and target is up to date.
+    async fn update_v6(&self, records: &[ZoneRecord], targets: &[Target]) -> bool {
+        let targets: Vec<&Target> = targets.iter().filter(|t| t.v6).collect();
+        if records.is_empty() && targets.is_empty() {
+            return true;
         }
 
         let mut groups: HashMap<&ProviderConfig, Vec<&ZoneRecord>> = HashMap::new();
@@ -210,19 +315,29 @@ impl Updater {
             let cfg = rec.lookup.as_ref().unwrap_or(&self.lookup_config.v6);
             groups.entry(cfg).or_default().push(rec);
         }
+        if !targets.is_empty() {
+            groups.entry(&self.lookup_config.v6).or_default();
+        }
 
         let futs: Vec<_> = groups
             .iter()
             .filter_map(|(cfg, recs)| {
+                let targets = if *cfg == &self.lookup_config.v6 {
+                    targets.as_slice()
+                } else {
+                    &[]
+                };
                 if let Some(provider) = self.providers.get(*cfg) {
-                    Some(self.update_v6_with_provider(provider, cfg, recs))
+                    Some(self.update_v6_with_provider(provider, cfg, recs, targets))
                 } else {
                     error!("Lookup provider unexpectedly missing for {cfg:?}");
                     None
                 }
             })
             .collect();
-        join_all(futs).await;
+        // a group without a provider could not be updated
+        let complete = futs.len() == groups.len();
+        join_all(futs).await.into_iter().all(|done| done) && complete
     }
 
     async fn update_v6_with_provider(
@@ -230,8 +345,11 @@ impl Updater {
         provider: &Provider,
         cache_key: &ProviderConfig,
         records: &[&ZoneRecord],
-    ) {
-        let mut staged: Option<Ipv6Addr> = None;
+        targets: &[&Target],
+    ) -> bool {
+        let mut staged: Option<Vec<IpAddr>> = None;
+        let mut pending: Vec<&ZoneRecord> = records.to_vec();
+        let mut pending_targets: Vec<&Target> = targets.to_vec();
         let mut attempt: u32 = 0;
 
         loop {
@@ -239,19 +357,21 @@ impl Updater {
 
             // Perform lookup only when we don't have a staged IP yet.
             if staged.is_none() {
-                match provider.lookup_v6().await {
-                    Ok(addr) => {
-                        match self.lookup_cache.borrow_mut().update_v6(cache_key, addr) {
-                            UpdateResult::Initialized => info!("Current IPv6: {addr}"),
+                match provider.lookup_all_v6().await {
+                    Ok(addrs) => {
+                        self.address_found(records, true);
+                        let current = join_addrs(&addrs, ", ");
+                        match self.lookup_cache.borrow_mut().update_v6(cache_key, &addrs) {
+                            UpdateResult::Initialized => info!("Current IPv6: {current}"),
                             UpdateResult::Updated(old) => {
-                                info!("Current IPv6: {addr} (was {old})");
+                                info!("Current IPv6: {current} (was {})", join_addrs(&old, ", "));
                             }
                             UpdateResult::Unchanged => {
-                                info!("Current IPv6: {addr} (unchanged, skipping update)");
-                                return;
+                                info!("Current IPv6: {current} (unchanged, skipping update)");
+                                return true;
                             }
                         }
-                        staged = Some(addr);
+                        staged = Some(addrs.into_iter().map(IpAddr::from).collect());
                     }
                     Err(e) => {
                         error!("Failed to lookup current IPv6 address: {e}");
@@ -260,16 +380,16 @@ impl Updater {
             }
 
             // DNS update with the staged IP (no re-lookup on retry).
-            if let Some(addr) = staged {
-                let results = join_all(
-                    records
-                        .iter()
-                        .map(|rec| self.update_record_print(rec, addr.into())),
-                )
-                .await;
+            if let Some(addrs) = &staged {
+                let (results, target_results) = join!(
+                    self.update_records_to(&pending, addrs, cache_key.returns_set()),
+                    self.update_targets(&pending_targets, addrs[0])
+                );
 
-                if results.iter().all(|&s| s) {
-                    return;
+                pending = retry_pending(pending, results);
+                pending_targets = retry_pending(pending_targets, target_results);
+                if pending.is_empty() && pending_targets.is_empty() {
+                    return true;
                 }
             }
 
@@ -289,99 +409,522 @@ impl Updater {
 
         if staged.is_some() {
             error!("IPv6 DNS update failed, giving up until next interval");
+            // The next cycle must retry the update even if the address did not change.
+            self.lookup_cache.borrow_mut().forget_v6(cache_key);
         } else {
             error!("IPv6 lookup failed, giving up until next interval");
+            self.address_missing(cache_key, records, true).await;
         }
+        false
     }
 
-    async fn update_record_print(&self, rec: &ZoneRecord, addr: IpAddr) -> bool {
-        let rec_type = match addr {
-            IpAddr::V4(_) => "A",
-            IpAddr::V6(_) => "AAAA",
-        };
-        info!("Updating {rec_type} record '{}'", rec.name);
-        if let Err(e) = self.update_record(rec, addr).await {
-            error!("Failed to update {rec_type} record '{}': {e}", rec.name);
-            false
-        } else {
-            info!("Updated {rec_type} record '{}'", rec.name);
-            true
+    /// Updates `records` to `addrs`. With `set`, address records are reconciled to hold exactly
+    /// `addrs`; otherwise, and for records of other types, the first address is used.
+    /// Returns the outcome for each record, in the same order as `records`.
+    async fn update_records_to(
+        &self,
+        records: &[&ZoneRecord],
+        addrs: &[IpAddr],
+        set: bool,
+    ) -> Vec<UpdateStatus> {
+        if !set {
+            return self.update_records_print(records, addrs[0]).await;
         }
+        let (sets, singles): (Vec<_>, Vec<_>) = records
+            .iter()
+            .enumerate()
+            .partition(|(_, rec)| rec.kind == RecordKind::Address);
+        let single_records: Vec<&ZoneRecord> = singles.iter().map(|(_, rec)| **rec).collect();
+        let (set_statuses, single_statuses) = join!(
+            join_all(
+                sets.iter()
+                    .map(|(_, rec)| self.update_record_set_print(rec, addrs))
+            ),
+            self.update_records_print(&single_records, addrs[0])
+        );
+
+        let mut statuses = vec![UpdateStatus::Retry; records.len()];
+        for ((i, _), status) in sets
+            .into_iter()
+            .chain(singles)
+            .zip(set_statuses.into_iter().chain(single_statuses))
+        {
+            statuses[i] = status;
+        }
+        statuses
     }
 
-    async fn update_record(&self, rec: &ZoneRecord, addr: IpAddr) -> Result<DnsRecord> {
-        let zone_id = self
-            .zone_id(&rec.zone)
-            .await
-            .context("Failed to get the zone identifier")?;
+    /// Updates `records` to `addr`, submitting the changes to each zone as a single batch.
+    /// Records of other types (HTTPS and SVCB hints, SPF policies) are rewritten in place,
+    /// record by record, instead.
+    /// Returns the outcome for each record, in the same order as `records`.
+    async fn update_records_print(
+        &self,
+        records: &[&ZoneRecord],
+        addr: IpAddr,
+    ) -> Vec<UpdateStatus> {
+        let mut statuses = vec![UpdateStatus::Retry; records.len()];
+        let (rewritten, addressed): (Vec<_>, Vec<_>) = records
+            .iter()
+            .enumerate()
+            .partition(|(_, rec)| rec.kind.rewritten_type().is_some());
+
+        // Resolve identifiers first, so that the changes can be grouped per zone.
+        let (rewritten, staged) = join!(
+            join_all(rewritten.into_iter().map(|(i, rec)| async move {
+                info!("Updating {} record '{}'", record_type(rec, addr), rec.name);
+                let status = match self.rewrite_record(rec, addr).await {
+                    Ok(()) => {
+                        info!("Updated {} record '{}'", record_type(rec, addr), rec.name);
+                        UpdateStatus::Done
+                    }
+                    Err(e) => report_failure(rec, addr, &e),
+                };
+                (i, status)
+            })),
+            join_all(addressed.into_iter().map(|(i, rec)| async move {
+                info!("Updating {} record '{}'", record_type(rec, addr), rec.name);
+                (i, self.stage_change(rec, addr).await)
+            }))
+        );
+        for (i, status) in rewritten {
+            statuses[i] = status;
+        }
+        let mut zones: HashMap<_, Vec<(usize, StagedChange)>> = HashMap::new();
+        for (i, result) in staged {
+            match result {
+                Ok(change) => zones
+                    .entry((change.account.scope, Rc::clone(&change.zone_id)))
+                    .or_default()
+                    .push((i, change)),
+                Err(e) => statuses[i] = report_failure(records[i], addr, &e),
+            }
+        }
+
+        let applied = join_all(
+            zones
+                .iter()
+                .map(|((_, zone_id), changes)| self.apply_changes(zone_id, changes)),
+        )
+        .await;
+        for (i, status) in applied.into_iter().flatten() {
+            statuses[i] = status;
+        }
+
+        // PTR records follow once the forward record points at the new address.
+        let ptrs = join_all(
+            records
+                .iter()
+                .enumerate()
+                .filter(|(i, rec)| {
+                    statuses[*i] == UpdateStatus::Done && !rec.reverse_zones.is_empty()
+                })
+                .map(|(i, rec)| async move { (i, self.update_ptr_print(rec, addr).await) }),
+        )
+        .await;
+        for (i, status) in ptrs {
+            statuses[i] = status;
+        }
+        statuses
+    }
+
+    fn account(&self, auth: Option<&Auth>) -> Account<'_> {
+        match auth.and_then(|auth| self.overrides.get(auth)) {
+            Some((cf, scope)) => Account {
+                cf,
+                scope: Some(scope),
+            },
+            None => Account {
+                cf: &self.cf,
+                scope: None,
+            },
+        }
+    }
+
+    async fn stage_change<'a>(
+        &'a self,
+        rec: &'a ZoneRecord,
+        addr: IpAddr,
+    ) -> Result<StagedChange<'a>> {
+        let account = self.account(rec.auth.as_ref());
+        let zone_id = self.record_zone_id(account, rec).await?;
         let rec_id = self
-            .record_id(&zone_id, &rec.name, &addr)
+            .record_id(account, &zone_id, rec, &addr)
             .await
             .context("Failed to get the record identifier")?;
-        let record = if let Some(rec_id) = rec_id {
-            self.cf
-                .update_record(&zone_id, &rec_id, &rec.name, addr)
-                .await
-                .context("Failed to update the record")?
-        } else {
-            let record = self
-                .cf
-                .create_record(&zone_id, &rec.name, addr)
-                .await
-                .context("Failed to create the record")?;
-            self.update_cache(&rec.name, &record)?;
-            record
+        Ok(StagedChange {
+            rec,
+            account,
+            zone_id,
+            rec_id,
+            addr,
+            settings: ownership::stamp(self.ownership.as_ref(), &rec.settings),
+        })
+    }
+
+    /// Rewrites the addresses within the existing records of `rec`.
+    async fn rewrite_record(&self, rec: &ZoneRecord, addr: IpAddr) -> Result<()> {
+        match rec.kind {
+            RecordKind::Https => self.update_hints(rec, "HTTPS", addr).await,
+            RecordKind::Svcb => self.update_hints(rec, "SVCB", addr).await,
+            RecordKind::Spf => self.update_spf(rec, addr).await,
+            RecordKind::Address => unreachable!("address records are staged as changes"),
+        }
+    }
+
+    /// Applies the staged changes of a single zone, all made with the same account, atomically in
+    /// one batch request when there is more than one. Falls back to one request per change if the
+    /// batch endpoint is unavailable.
+    async fn apply_changes(
+        &self,
+        zone_id: &str,
+        changes: &[(usize, StagedChange<'_>)],
+    ) -> Vec<(usize, UpdateStatus)> {
+        let Some((_, first)) = changes.first() else {
+            return Vec::new();
         };
+        let cf = first.account.cf;
+        if changes.len() > 1 && !self.batch_unavailable.get() {
+            let batch: Vec<_> = changes.iter().map(|(_, c)| c.change()).collect();
+            match cf.batch_changes(zone_id, &batch).await {
+                Ok(records) => {
+                    return changes
+                        .iter()
+                        .zip(records)
+                        .map(|((i, change), record)| (*i, self.report_applied(change, &record)))
+                        .collect();
+                }
+                Err(e) if error::is_unsupported(&e) => {
+                    warn!("Batch DNS updates are unavailable, updating records one by one: {e:#}");
+                    self.batch_unavailable.set(true);
+                }
+                Err(e) => {
+                    let e = e.context("Failed to apply the batch of changes");
+                    return changes
+                        .iter()
+                        .map(|(i, change)| (*i, report_failure(change.rec, change.addr, &e)))
+                        .collect();
+                }
+            }
+        }
 
-        Ok(record)
+        join_all(changes.iter().map(|(i, change)| async move {
+            let status = match cf.apply_change(zone_id, &change.change()).await {
+                Ok(record) => self.report_applied(change, &record),
+                Err(e) => {
+                    let e = e.context(if change.rec_id.is_some() {
+                        "Failed to update the record"
+                    } else {
+                        "Failed to create the record"
+                    });
+                    report_failure(change.rec, change.addr, &e)
+                }
+            };
+            (*i, status)
+        }))
+        .await
+    }
+
+    fn report_applied(&self, change: &StagedChange, record: &DnsRecord) -> UpdateStatus {
+        if change.rec_id.is_none()
+            && let Err(e) = self.update_cache(change.account, &change.rec.name, record)
+        {
+            warn!("Failed to save the ID cache: {e:#}");
+        }
+        info!(
+            "Updated {} record '{}'",
+            record_type(change.rec, change.addr),
+            change.rec.name
+        );
+        UpdateStatus::Done
+    }
+}
+
+/// A record change with its identifiers resolved, ready to be applied.
+struct StagedChange<'a> {
+    rec: &'a ZoneRecord,
+    account: Account<'a>,
+    zone_id: Rc<str>,
+    /// `None` if the record does not exist yet and will be created.
+    rec_id: Option<Rc<str>>,
+    addr: IpAddr,
+    settings: Cow<'a, RecordSettings>,
+}
+
+impl StagedChange<'_> {
+    fn change(&self) -> RecordChange<'_> {
+        match &self.rec_id {
+            Some(id) => RecordChange::Update {
+                id,
+                name: &self.rec.name,
+                addr: self.addr,
+                settings: &self.settings,
+            },
+            None => RecordChange::Create {
+                name: &self.rec.name,
+                addr: self.addr,
+                settings: &self.settings,
+            },
+        }
+    }
+}
+
+const fn record_type(rec: &ZoneRecord, addr: IpAddr) -> &'static str {
+    match (rec.kind, addr) {
+        (RecordKind::Address, IpAddr::V4(_)) => "A",
+        (RecordKind::Address, IpAddr::V6(_)) => "AAAA",
+        (RecordKind::Https, _) => "HTTPS",
+        (RecordKind::Svcb, _) => "SVCB",
+        (RecordKind::Spf, _) => "SPF",
+    }
+}
+
+/// Keeps the items whose update failed transiently, as only those are worth another attempt.
+fn retry_pending<T>(pending: Vec<T>, statuses: Vec<UpdateStatus>) -> Vec<T> {
+    pending
+        .into_iter()
+        .zip(statuses)
+        .filter_map(|(item, status)| (status == UpdateStatus::Retry).then_some(item))
+        .collect()
+}
+
+/// Logs a failed record update and decides whether it is worth retrying.
+fn report_failure(rec: &ZoneRecord, addr: IpAddr, e: &anyhow::Error) -> UpdateStatus {
+    let rec_type = record_type(rec, addr);
+    if ownership::is_unowned(e) {
+        warn!("Skipping {rec_type} record '{}': {e:#}", rec.name);
+        UpdateStatus::Failed
+    } else if error::is_permanent(e) || conflict::is_conflict(e) {
+        error!(
+            "Failed to update {rec_type} record '{}': {e:#} (not retrying)",
+            rec.name
+        );
+        UpdateStatus::Failed
+    } else {
+        error!("Failed to update {rec_type} record '{}': {e:#}", rec.name);
+        UpdateStatus::Retry
     }
 }
 
 impl Updater {
-    async fn zone_id(&self, zone: &str) -> Result<Rc<str>> {
-        let res = self.id_cache.borrow().get_zone(zone);
-        match res {
-            Some(id) => return Ok(id),
-            None => self.cache_zones().await?,
+    async fn zone_id(&self, account: Account<'_>, zone: &str) -> Result<Rc<str>> {
+        let res = self.id_cache.borrow().get_zone(account.scope, zone);
+        if let Some(id) = res {
+            return Ok(id);
+        }
+        if self.id_cache.borrow().is_zone_missing(account.scope, zone) {
+            bail!("Cannot find zone: {zone} (not found recently, will look up again later)");
         }
-        self.id_cache
-            .borrow()
-            .get_zone(zone)
-            .ok_or_else(|| anyhow!("Cannot find zone: {zone}"))
+        self.cache_zone(account, zone).await?;
+        let res = self.id_cache.borrow().get_zone(account.scope, zone);
+        res.ok_or_else(|| {
+            anyhow!(
+                "Cannot find zone: {zone}. Check the zone name, or set `zone_id` \
+                 if the token cannot list zones"
+            )
+        })
     }
 
-    async fn record_id(&self, zone_id: &str, name: &str, addr: &IpAddr) -> Result<Option<Rc<str>>> {
-        if self.id_cache.borrow().get_record(name, addr).is_none() {
-            self.cache_records(zone_id, name).await?;
+    /// The configured `zone_id`, or the identifier of `zone` looked up by name.
+    async fn configured_zone_id(
+        &self,
+        account: Account<'_>,
+        zone: &str,
+        zone_id: Option<&str>,
+    ) -> Result<Rc<str>> {
+        match zone_id {
+            Some(id) => Ok(Rc::from(id)),
+            None => self
+                .zone_id(account, zone)
+                .await
+                .context("Failed to get the zone identifier"),
         }
-        Ok(self.id_cache.borrow().get_record(name, addr))
     }
 
-    fn update_cache(&self, name: &str, record: &DnsRecord) -> Result<()> {
-        let mut cache = self.id_cache.borrow_mut();
-        cache.update_record(name, record);
-        cache.save()
+    /// The zone identifier of `rec`: its `zone_id`, or the identifier of its configured zone,
+    /// or else of the zone inferred from its name.
+    async fn record_zone_id(&self, account: Account<'_>, rec: &ZoneRecord) -> Result<Rc<str>> {
+        if let Some(id) = &rec.zone_id {
+            return Ok(Rc::from(id.as_str()));
+        }
+        let zone = match &rec.zone {
+            Some(zone) => Rc::from(zone.as_str()),
+            None => self.infer_zone(account, &rec.name).await?,
+        };
+        self.zone_id(account, &zone)
+            .await
+            .context("Failed to get the zone identifier")
     }
 
-    async fn cache_zones(&self) -> Result<()> {
-        let zones = self.cf.list_zones().await?;
+    /// The most specific zone visible to the credentials of `account` that holds `name`.
+    /// Zones are listed once per record name and credentials.
+    async fn infer_zone(&self, account: Account<'_>, name: &str) -> Result<Rc<str>> {
+        let res = self.id_cache.borrow().get_record_zone(account.scope, name);
+        if let Some(zone) = res {
+            return Ok(zone);
+        }
+        let found = preflight::zone_holding(account.cf, name).await?;
+        info!("Using zone '{}' for record '{name}'", found.name);
+        let zone: Rc<str> = Rc::from(found.name.as_str());
         let mut cache = self.id_cache.borrow_mut();
-        for zone in zones {
-            cache.save_zone(zone.name, zone.id);
+        cache.save_zone(account.scope, found.name, found.id);
+        cache.save_record_zone(account.scope, name, Rc::clone(&zone));
+        cache.save()?;
+        Ok(zone)
+    }
+
+    async fn record_id(
+        &self,
+        account: Account<'_>,
+        zone_id: &str,
+        rec: &ZoneRecord,
+        addr: &IpAddr,
+    ) -> Result<Option<Rc<str>>> {
+        let scope = account.scope;
+        // Listed on every update, even with the identifier cached, so that duplicates added since
+        // the last update are found.
+        let unowned = self.cache_records(account, zone_id, rec, addr).await?;
+        let id = self.id_cache.borrow().get_record(scope, &rec.name, addr);
+        // Creating a record next to one we don't own would leave both in place.
+        if id.is_none() && unowned.get_for(addr).is_some() {
+            return Err(ownership::UnownedRecord.into());
         }
+        Ok(id)
+    }
+
+    fn update_cache(&self, account: Account<'_>, name: &str, record: &DnsRecord) -> Result<()> {
+        let mut cache = self.id_cache.borrow_mut();
+        cache.update_record(account.scope, name, record);
         cache.save()
     }
 
-    async fn cache_records(&self, zone_id: &str, name: &str) -> Result<()> {
-        let records = self.cf.list_records(zone_id, name).await?;
+    async fn cache_zone(&self, account: Account<'_>, zone: &str) -> Result<()> {
+        let found = account.cf.find_zone(zone).await?;
         let mut cache = self.id_cache.borrow_mut();
-        for rec in &records {
-            cache.update_record(name, rec);
+        if let Some(found) = found {
+            // keyed by the configured name, which may differ in case
+            cache.save_zone(account.scope, zone.to_owned(), found.id);
+            cache.save()
+        } else {
+            cache.mark_zone_missing(account.scope, zone);
+            Ok(())
+        }
+    }
+
+    /// Caches the identifiers of the records managed for `rec`, returning the ones that were
+    /// skipped because they lack the ownership marker.
+    ///
+    /// Only the first record of each address family is managed; the others are duplicates.
+    /// Those of the family of `addr`, the one being updated, are reported or deleted as
+    /// `rec.duplicates` says.
+    async fn cache_records(
+        &self,
+        account: Account<'_>,
+        zone_id: &str,
+        rec: &ZoneRecord,
+        addr: &IpAddr,
+    ) -> Result<RecordIdCache> {
+        let records = account.cf.list_records(zone_id, &rec.name).await?;
+        let mut unowned = RecordIdCache::default();
+        let mut owned = Vec::new();
+        for record in &records {
+            match &self.ownership {
+                Some(marker) if !rec.adopt && !ownership::is_owned(marker, record) => {
+                    unowned.update(record);
+                }
+                _ => owned.push(record),
+            }
+        }
+        let (managed, duplicates) = split_duplicates(owned);
+        let mut ids = RecordIdCache::default();
+        for record in managed {
+            // Stamp adopted records, so that they are recognized once `adopt` is unset.
+            if let Some(settings) = ownership::adoption(self.ownership.as_ref(), record)
+                && let Some(addr) = record.content.address()
+            {
+                account
+                    .cf
+                    .update_record(zone_id, &record.id, &rec.name, addr, &settings)
+                    .await
+                    .context("Failed to add the ownership marker to the adopted record")?;
+                info!("Adopted {} record '{}'", record_type(rec, addr), rec.name);
+            }
+            ids.update(record);
+        }
+        // A record deleted since it was cached must not be updated, but created again.
+        {
+            let mut cache = self.id_cache.borrow_mut();
+            cache.save_records(account.scope, &rec.name, ids);
+            cache.save()?;
+        }
+        let same_family = |record: &&DnsRecord| {
+            record
+                .content
+                .address()
+                .is_some_and(|found| found.is_ipv4() == addr.is_ipv4())
+        };
+        for record in duplicates.into_iter().filter(same_family) {
+            self.handle_duplicate(account, zone_id, rec, record).await;
+        }
+        Ok(unowned)
+    }
+
+    /// Reports or deletes `record`, an extra A or AAAA record next to the one managed for `rec`.
+    async fn handle_duplicate(
+        &self,
+        account: Account<'_>,
+        zone_id: &str,
+        rec: &ZoneRecord,
+        record: &DnsRecord,
+    ) {
+        let Some(addr) = record.content.address() else {
+            return;
+        };
+        let rec_type = record_type(rec, addr);
+        match rec.duplicates {
+            Duplicates::Report => error!(
+                "Found an extra {rec_type} record '{}' pointing at {addr}, which clients may \
+                 still be sent to; delete it or set `duplicates = \"delete\"`",
+                rec.name
+            ),
+            Duplicates::Delete => match account.cf.delete_record(zone_id, &record.id).await {
+                // deleted concurrently, e.g. by another update of the same name
+                Err(e) if error::is_not_found(&e) => {}
+                Ok(()) => warn!(
+                    "Deleted extra {rec_type} record '{}' pointing at {addr}",
+                    rec.name
+                ),
+                Err(e) => error!(
+                    "Failed to delete extra {rec_type} record '{}': {e:#}",
+                    rec.name
+                ),
+            },
         }
-        cache.save()
     }
 }
 
+/// Splits `records` into the first A and AAAA record, which are managed, and the extra ones.
+fn split_duplicates(records: Vec<&DnsRecord>) -> (Vec<&DnsRecord>, Vec<&DnsRecord>) {
+    let (mut v4, mut v6) = (false, false);
+    records.into_iter().partition(|record| {
+        let seen = match record.content.address() {
+            Some(IpAddr::V4(_)) => &mut v4,
+            Some(IpAddr::V6(_)) => &mut v6,
+            None => return true,
+        };
+        !std::mem::replace(seen, true)
+    })
+}
+
+/// Formats `addrs` separated by `sep`.
+fn join_addrs<T: fmt::Display>(addrs: &[T], sep: &str) -> String {
+    addrs
+        .iter()
+        .map(ToString::to_string)
+        .collect::<Vec<_>>()
+        .join(sep)
+}
+
 /// Calculates the backoff delay for a given attempt number.
 ///
 /// Formula: `base_delay * multiplier^(attempt - 1)`.
@@ -467,9 +1010,32 @@ mod tests {
         let custom_cfg = ProviderConfig::Interface {
             interface: String::new(),
             matchers: crate::config::MatcherConfig::default(),
+            all: false,
         };
         assert!(!updater.providers.contains_key(&custom_cfg));
 
         Ok(())
     }
+
+    #[test]
+    fn split_duplicates_keeps_first_of_each_family() -> Result<()> {
+        let records: Vec<DnsRecord> = serde_json::from_str(
+            r#"[
+                {"id": "a1", "name": "home.example.com", "type": "A", "content": "192.0.2.1"},
+                {"id": "a2", "name": "home.example.com", "type": "A", "content": "192.0.2.2"},
+                {"id": "q1", "name": "home.example.com", "type": "AAAA", "content": "2001:db8::1"},
+                {"id": "a3", "name": "home.example.com", "type": "A", "content": "192.0.2.3"}
+            ]"#,
+        )?;
+        let (managed, duplicates) = split_duplicates(records.iter().collect());
+        let ids = |records: Vec<&DnsRecord>| -> Vec<String> {
+            records
+                .into_iter()
+                .map(|record| record.id.clone())
+                .collect()
+        };
+        assert_eq!(ids(managed), ["a1", "q1"]);
+        assert_eq!(ids(duplicates), ["a2", "a3"]);
+        Ok(())
+    }
 }
@@ -0,0 +1,178 @@
+//! Ownership markers that keep cf-ddns away from records it does not manage
+
+use std::borrow::Cow;
+use std::fmt;
+
+use crate::cloudflare::record::DnsRecord;
+use crate::config::{OwnershipMarker, RecordSettings};
+
+/// An existing record lacks the ownership marker, and its entry does not allow adopting it.
+#[derive(Debug)]
+pub struct UnownedRecord;
+
+impl fmt::Display for UnownedRecord {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(
+            "an existing record is not managed by cf-ddns (ownership marker missing); \
+             set `adopt = true` on the record entry to take it over",
+        )
+    }
+}
+
+impl std::error::Error for UnownedRecord {}
+
+/// Returns `true` if `err` was caused by [`UnownedRecord`].
+pub fn is_unowned(err: &anyhow::Error) -> bool {
+    err.chain()
+        .any(|cause| cause.downcast_ref::<UnownedRecord>().is_some())
+}
+
+/// Whether `record` carries the ownership marker.
+pub fn is_owned(marker: &OwnershipMarker, record: &DnsRecord) -> bool {
+    match marker {
+        OwnershipMarker::Tag(tag) => record.tags.contains(tag),
+        OwnershipMarker::Comment(comment) => record
+            .comment
+            .as_deref()
+            .is_some_and(|c| c.contains(comment.as_str())),
+    }
+}
+
+/// The existing `records` that cf-ddns may rewrite in place: the ones carrying the marker, or
+/// all of them without a marker or when the entry adopts them. Fails if none of them qualify.
+pub fn rewritable<'a>(
+    marker: Option<&OwnershipMarker>,
+    adopt: bool,
+    records: &'a [DnsRecord],
+) -> Result<Vec<&'a DnsRecord>, UnownedRecord> {
+    let owned: Vec<_> = records
+        .iter()
+        .filter(|record| adopt || marker.is_none_or(|marker| is_owned(marker, record)))
+        .collect();
+    if owned.is_empty() && !records.is_empty() {
+        return Err(UnownedRecord);
+    }
+    Ok(owned)
+}
+
+/// Adds the ownership marker to the settings applied to created (and enforced) records.
+pub fn stamp<'a>(
+    marker: Option<&OwnershipMarker>,
+    settings: &'a RecordSettings,
+) -> Cow<'a, RecordSettings> {
+    let Some(marker) = marker else {
+        return Cow::Borrowed(settings);
+    };
+    match marker {
+        OwnershipMarker::Tag(tag) if settings.tags.contains(tag) => Cow::Borrowed(settings),
+        OwnershipMarker::Tag(tag) => {
+            let mut tags = settings.tags.clone();
+            tags.push(tag.clone());
+            Cow::Owned(RecordSettings {
+                tags,
+                ..settings.clone()
+            })
+        }
+        OwnershipMarker::Comment(marker) => {
+            let comment = match settings.comment.as_deref() {
+                Some(c) if c.contains(marker.as_str()) => return Cow::Borrowed(settings),
+                Some(c) => format!("{c} {marker}"),
+                None => marker.clone(),
+            };
+            Cow::Owned(RecordSettings {
+                comment: Some(comment),
+                ..settings.clone()
+            })
+        }
+    }
+}
+
+/// Settings adding the marker to `record`, an existing record being adopted, while keeping its
+/// comment and other tags. `None` when it already carries the marker.
+pub fn adoption(marker: Option<&OwnershipMarker>, record: &DnsRecord) -> Option<RecordSettings> {
+    let marker = marker.filter(|marker| !is_owned(marker, record))?;
+    let current = RecordSettings {
+        comment: record.comment.clone(),
+        tags: record.tags.clone(),
+        // only the comment and tags are sent
+        enforce: true,
+        ..RecordSettings::default()
+    };
+    Some(stamp(Some(marker), &current).into_owned())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn record(json: &str) -> DnsRecord {
+        serde_json::from_str(json).expect("valid record")
+    }
+
+    #[test]
+    fn adoption_keeps_comment_and_tags() {
+        let rec = record(
+            r#"{"id":"1","name":"a.example.com","type":"A","content":"192.0.2.1",
+                "comment":"home","tags":["owner:me"]}"#,
+        );
+        let marker = OwnershipMarker::Tag("cf-ddns:managed".to_owned());
+        let settings = adoption(Some(&marker), &rec).expect("marker missing");
+        assert_eq!(settings.comment.as_deref(), Some("home"));
+        assert_eq!(settings.tags, ["owner:me", "cf-ddns:managed"]);
+        assert!(settings.enforce && settings.ttl.is_none() && settings.proxied.is_none());
+
+        let marker = OwnershipMarker::Comment("cf-ddns:managed".to_owned());
+        let settings = adoption(Some(&marker), &rec).expect("marker missing");
+        assert_eq!(settings.comment.as_deref(), Some("home cf-ddns:managed"));
+        assert_eq!(settings.tags, ["owner:me"]);
+
+        assert!(adoption(None, &rec).is_none());
+    }
+
+    #[test]
+    fn owned_by_tag_or_comment() {
+        let rec = record(
+            r#"{"id":"1","name":"a.example.com","type":"A","content":"192.0.2.1",
+                "comment":"home cf-ddns:managed","tags":["cf-ddns:managed"]}"#,
+        );
+        assert!(is_owned(
+            &OwnershipMarker::Tag("cf-ddns:managed".to_owned()),
+            &rec
+        ));
+        assert!(is_owned(
+            &OwnershipMarker::Comment("cf-ddns:managed".to_owned()),
+            &rec
+        ));
+
+        let rec = record(r#"{"id":"1","name":"a.example.com","type":"A","content":"192.0.2.1"}"#);
+        assert!(!is_owned(
+            &OwnershipMarker::Tag("cf-ddns:managed".to_owned()),
+            &rec
+        ));
+        assert!(!is_owned(
+            &OwnershipMarker::Comment("cf-ddns:managed".to_owned()),
+            &rec
+        ));
+    }
+
+    #[test]
+    fn stamp_adds_marker_once() {
+        let settings = RecordSettings {
+            comment: Some("home".to_owned()),
+            tags: vec!["owner:home".to_owned()],
+            ..RecordSettings::default()
+        };
+
+        let marker = OwnershipMarker::Tag("cf-ddns:managed".to_owned());
+        let stamped = stamp(Some(&marker), &settings);
+        assert_eq!(stamped.tags, ["owner:home", "cf-ddns:managed"]);
+        assert_eq!(stamp(Some(&marker), &stamped).tags, stamped.tags);
+
+        let marker = OwnershipMarker::Comment("cf-ddns:managed".to_owned());
+        let stamped = stamp(Some(&marker), &settings);
+        assert_eq!(stamped.comment.as_deref(), Some("home cf-ddns:managed"));
+        assert_eq!(stamp(Some(&marker), &stamped).comment, stamped.comment);
+
+        assert!(matches!(stamp(None, &settings), Cow::Borrowed(_)));
+    }
+}
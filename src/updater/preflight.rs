@@ -0,0 +1,199 @@
+//! Startup checks for the configured credentials and zones
+
+use std::collections::HashSet;
+
+use anyhow::{Context, Result, anyhow, bail};
+use tracing::{info, warn};
+
+use crate::cloudflare::CloudFlare;
+use crate::cloudflare::error;
+use crate::cloudflare::zone::Zone;
+use crate::config::{Auth, Records};
+use crate::updater::Updater;
+use crate::util::network::longest_zone;
+
+impl Updater {
+    /// Verifies the credentials and every configured zone before the first update, so a bad or
+    /// under-scoped token fails fast instead of surfacing as a failed update later on.
+    ///
+    /// Network errors and transient API failures only produce a warning, so a service starting
+    /// before the network is up still gets going.
+    pub async fn preflight(&self, records: &Records) -> Result<()> {
+        verify_token(&self.cf, "API token").await?;
+
+        let mut verified = HashSet::new();
+        for configured in configured_zones(records) {
+            let zone = configured.zone;
+            let account = self.account(configured.auth);
+            if let Some(scope) = account.scope
+                && verified.insert(scope)
+            {
+                verify_token(account.cf, &format!("API token for zone '{zone}'")).await?;
+            }
+            match check_zone(account.cf, zone, configured.zone_id).await {
+                Ok(found) => {
+                    info!("Zone '{zone}' is reachable");
+                    if configured.zone_id.is_none() {
+                        let mut cache = self.id_cache.borrow_mut();
+                        cache.save_zone(account.scope, zone.to_owned(), found.id);
+                        cache.save()?;
+                    }
+                }
+                Err(e) if error::is_transient(&e) => {
+                    warn!("Unable to check zone '{zone}', continuing anyway: {e:#}");
+                }
+                Err(e) => return Err(e),
+            }
+        }
+
+        for rec in records.v4.iter().chain(&records.v6) {
+            if rec.zone.is_some() || rec.zone_id.is_some() {
+                continue;
+            }
+            let account = self.account(rec.auth.as_ref());
+            match self.infer_zone(account, &rec.name).await {
+                Ok(_) => {}
+                Err(e) if error::is_transient(&e) => {
+                    warn!(
+                        "Unable to infer the zone of record '{}', continuing anyway: {e:#}",
+                        rec.name
+                    );
+                }
+                Err(e) => return Err(e),
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Checks that the API token of `cf` is active. Does nothing for Global API Key credentials.
+async fn verify_token(cf: &CloudFlare, label: &str) -> Result<()> {
+    if !cf.uses_token() {
+        return Ok(());
+    }
+    match cf.verify_token().await {
+        Ok(token) if token.is_active() => {
+            let expiry = token.expires_on.as_deref().unwrap_or("never");
+            info!("{label} is active (expires: {expiry})");
+        }
+        Ok(token) => bail!("{label} is {}", token.status),
+        Err(e) if error::is_transient(&e) => {
+            warn!("Unable to verify {label}, continuing anyway: {e:#}");
+        }
+        Err(e) => return Err(e.context(format!("{label} verification failed"))),
+    }
+    Ok(())
+}
+
+/// A zone referenced by the configured records, with the credentials used for it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ConfiguredZone<'a> {
+    pub zone: &'a str,
+    pub zone_id: Option<&'a str>,
+    /// `None` when the global credentials are used.
+    pub auth: Option<&'a Auth>,
+}
+
+/// Distinct zones referenced by `records`, in configuration order. Records configured with only
+/// a `zone_id` are labelled with it; records with neither have their zone inferred instead.
+pub fn configured_zones(records: &Records) -> Vec<ConfiguredZone<'_>> {
+    let mut zones = Vec::new();
+    for rec in records.v4.iter().chain(&records.v6) {
+        let Some(zone) = rec.zone.as_deref().or(rec.zone_id.as_deref()) else {
+            continue;
+        };
+        let entry = ConfiguredZone {
+            zone,
+            zone_id: rec.zone_id.as_deref(),
+            auth: rec.auth.as_ref(),
+        };
+        if !zones.contains(&entry) {
+            zones.push(entry);
+        }
+    }
+    zones
+}
+
+/// Looks up a configured zone and checks that the credentials can edit its DNS records.
+///
+/// Edit permission is only checked when Cloudflare reports the zone permissions;
+/// otherwise a reachable zone is assumed to be editable.
+pub async fn check_zone(cf: &CloudFlare, zone: &str, zone_id: Option<&str>) -> Result<Zone> {
+    let found = match zone_id {
+        Some(id) => cf
+            .get_zone(id)
+            .await
+            .with_context(|| format!("Cannot access zone '{zone}' ({id})"))?,
+        None => cf
+            .find_zone(zone)
+            .await
+            .with_context(|| format!("Cannot look up zone '{zone}'"))?
+            .ok_or_else(|| {
+                anyhow!(
+                    "Cannot find zone: {zone}. Check the zone name, or set `zone_id` \
+                     if the token cannot list zones"
+                )
+            })?,
+    };
+    if found.can_edit_dns() == Some(false) {
+        bail!("Missing DNS edit permission for zone '{zone}'");
+    }
+    Ok(found)
+}
+
+/// Finds the most specific zone visible to `cf` that holds `name`, and checks that the
+/// credentials can edit its DNS records.
+pub async fn zone_holding(cf: &CloudFlare, name: &str) -> Result<Zone> {
+    let mut zones = cf
+        .list_zones(None)
+        .await
+        .with_context(|| format!("Cannot list zones to infer the zone of '{name}'"))?;
+    let index = longest_zone(zones.iter().map(|zone| zone.name.as_str()), name)
+        .and_then(|zone| zones.iter().position(|found| found.name == zone))
+        .ok_or_else(|| {
+            anyhow!("None of the zones visible to the token holds '{name}', set `zone` explicitly")
+        })?;
+    let found = zones.swap_remove(index);
+    if found.can_edit_dns() == Some(false) {
+        bail!("Missing DNS edit permission for zone '{}'", found.name);
+    }
+    Ok(found)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Config;
+
+    #[test]
+    fn configured_zones_are_distinct() -> Result<()> {
+        let config = Config::from_toml(
+            r#"
+                token = "test"
+                [[records]]
+                name = "a.example.com"
+                zone = "example.com"
+                v4 = true
+                v6 = true
+                [[records]]
+                name = "b.example.com"
+                zone = "example.com"
+                v4 = true
+                [[records]]
+                name = "a.example.net"
+                zone = "example.net"
+                zone_id = "net-id"
+                v6 = true
+            "#,
+        )?;
+        let zones: Vec<_> = configured_zones(&config.records)
+            .into_iter()
+            .map(|z| (z.zone, z.zone_id))
+            .collect();
+        assert_eq!(
+            zones,
+            [("example.com", None), ("example.net", Some("net-id"))]
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,177 @@
+//! PTR records in Cloudflare-hosted reverse zones
+
+use std::net::IpAddr;
+
+use anyhow::{Context, Result, anyhow};
+use tracing::{error, info, warn};
+
+use crate::cloudflare::error;
+use crate::cloudflare::record::{DnsContent, DnsRecord};
+use crate::config::{RecordSettings, ZoneRecord};
+use crate::updater::{Account, UpdateStatus, Updater, ownership};
+use crate::util::network::{longest_zone, reverse_name};
+
+impl Updater {
+    /// Updates the PTR record of `addr`, logging a failure. A failure leaves the record pending,
+    /// so that the PTR record is retried along with it even though its address is up to date.
+    pub(super) async fn update_ptr_print(&self, rec: &ZoneRecord, addr: IpAddr) -> UpdateStatus {
+        let Err(e) = self.update_ptr(rec, addr).await else {
+            return UpdateStatus::Done;
+        };
+        if error::is_permanent(&e) || ownership::is_unowned(&e) {
+            error!(
+                "Failed to update the PTR record of '{}': {e:#} (not retrying)",
+                rec.name
+            );
+            UpdateStatus::Failed
+        } else {
+            error!("Failed to update the PTR record of '{}': {e:#}", rec.name);
+            UpdateStatus::Retry
+        }
+    }
+
+    /// Points the PTR record of `addr` at `rec.name`, then removes the ones written for previous
+    /// addresses. Their names are remembered in the ID cache until they are deleted.
+    async fn update_ptr(&self, rec: &ZoneRecord, addr: IpAddr) -> Result<()> {
+        let key = ptr_key(rec, addr.is_ipv6());
+        let name = reverse_name(addr);
+        let zone = longest_zone(rec.reverse_zones.iter().map(String::as_str), &name)
+            .ok_or_else(|| anyhow!("None of the `reverse_zones` holds '{name}'"))?;
+        let account = self.account(rec.auth.as_ref());
+        let zone_id = self
+            .zone_id(account, zone)
+            .await
+            .context("Failed to get the reverse zone identifier")?;
+
+        let records = self.ptr_records(account, &zone_id, &name).await?;
+        let content = DnsContent::PTR {
+            content: rec.name.clone(),
+        };
+        let owned = ownership::rewritable(self.ownership.as_ref(), rec.adopt, &records)?;
+        match owned.first() {
+            Some(record) if points_at(record, &rec.name) => {}
+            Some(record) => {
+                account
+                    .cf
+                    .update_record_content(&zone_id, &record.id, &name, content)
+                    .await
+                    .context("Failed to update the PTR record")?;
+            }
+            None => {
+                // PTR records cannot be proxied
+                let settings = RecordSettings {
+                    proxied: None,
+                    ..rec.settings.clone()
+                };
+                let settings = ownership::stamp(self.ownership.as_ref(), &settings);
+                account
+                    .cf
+                    .create_record_content(&zone_id, &name, content, &settings)
+                    .await
+                    .context("Failed to create the PTR record")?;
+            }
+        }
+        info!("Updated PTR record '{name}'");
+
+        let mut stale = self.written_ptrs(&key);
+        stale.retain(|previous| *previous != name);
+        let mut written = vec![name];
+        written.extend(self.delete_ptrs(rec, account, stale).await);
+
+        let mut cache = self.id_cache.borrow_mut();
+        cache.save_target_value(key, written.join(" "));
+        cache.save()
+    }
+
+    /// Deletes the PTR records cf-ddns wrote for the IPv4 or IPv6 address of `rec`, e.g. once
+    /// its address records are gone. Names that fail to delete are kept to try again later.
+    pub(super) async fn delete_written_ptrs(&self, rec: &ZoneRecord, v6: bool) -> Result<()> {
+        let key = ptr_key(rec, v6);
+        let written = self.written_ptrs(&key);
+        if written.is_empty() {
+            return Ok(());
+        }
+        let account = self.account(rec.auth.as_ref());
+        let kept = self.delete_ptrs(rec, account, written).await;
+
+        let mut cache = self.id_cache.borrow_mut();
+        if kept.is_empty() {
+            cache.remove_target_value(&key);
+        } else {
+            cache.save_target_value(key, kept.join(" "));
+        }
+        cache.save()
+    }
+
+    /// Names of the PTR records cf-ddns wrote and has not deleted yet, as kept under `key`.
+    fn written_ptrs(&self, key: &str) -> Vec<String> {
+        self.id_cache
+            .borrow()
+            .get_target_value(key)
+            .map(|names| names.split_whitespace().map(str::to_owned).collect())
+            .unwrap_or_default()
+    }
+
+    /// Deletes the PTR records at `names`, returning the names that failed, to try again later.
+    async fn delete_ptrs(
+        &self,
+        rec: &ZoneRecord,
+        account: Account<'_>,
+        names: impl IntoIterator<Item = String>,
+    ) -> Vec<String> {
+        let mut kept = Vec::new();
+        for name in names {
+            if let Err(e) = self.delete_ptr(rec, account, &name).await {
+                warn!("Failed to delete the previous PTR record '{name}': {e:#}");
+                kept.push(name);
+            }
+        }
+        kept
+    }
+
+    /// Deletes the PTR records at `name` pointing at `rec.name`, if cf-ddns may manage them.
+    async fn delete_ptr(&self, rec: &ZoneRecord, account: Account<'_>, name: &str) -> Result<()> {
+        let Some(zone) = longest_zone(rec.reverse_zones.iter().map(String::as_str), name) else {
+            // the zone is no longer configured
+            return Ok(());
+        };
+        let zone_id = self
+            .zone_id(account, zone)
+            .await
+            .context("Failed to get the reverse zone identifier")?;
+        let records = self.ptr_records(account, &zone_id, name).await?;
+        let Ok(owned) = ownership::rewritable(self.ownership.as_ref(), rec.adopt, &records) else {
+            return Ok(());
+        };
+        for record in owned {
+            if points_at(record, &rec.name) {
+                account.cf.delete_record(&zone_id, &record.id).await?;
+                info!("Deleted PTR record '{name}'");
+            }
+        }
+        Ok(())
+    }
+
+    async fn ptr_records(
+        &self,
+        account: Account<'_>,
+        zone_id: &str,
+        name: &str,
+    ) -> Result<Vec<DnsRecord>> {
+        account
+            .cf
+            .list_records_of_type(zone_id, name, "PTR")
+            .await
+            .context("Failed to list the PTR records")
+    }
+}
+
+/// ID cache key of the PTR records written for the IPv4 or IPv6 address of `rec`.
+fn ptr_key(rec: &ZoneRecord, v6: bool) -> String {
+    let family = if v6 { "v6" } else { "v4" };
+    format!("ptr:{}:{family}", rec.name)
+}
+
+fn points_at(record: &DnsRecord, name: &str) -> bool {
+    matches!(&record.content, DnsContent::PTR { content } if content == name)
+}
@@ -0,0 +1,156 @@
+//! A and AAAA records published as a set, from providers returning every address
+
+use std::net::IpAddr;
+
+use anyhow::{Context, Result};
+use tracing::info;
+
+use crate::cloudflare::record::DnsRecord;
+use crate::config::ZoneRecord;
+use crate::updater::{UpdateStatus, Updater, join_addrs, ownership, record_type, report_failure};
+
+impl Updater {
+    /// Updates the records of `rec` to hold exactly `addrs`, logging the outcome.
+    /// The PTR record, if any, follows the first address.
+    pub(super) async fn update_record_set_print(
+        &self,
+        rec: &ZoneRecord,
+        addrs: &[IpAddr],
+    ) -> UpdateStatus {
+        let rec_type = record_type(rec, addrs[0]);
+        info!("Updating {rec_type} record set '{}'", rec.name);
+        if let Err(e) = self.update_record_set(rec, addrs).await {
+            return report_failure(rec, addrs[0], &e);
+        }
+        info!(
+            "Updated {rec_type} record set '{}': {}",
+            rec.name,
+            join_addrs(addrs, ", ")
+        );
+
+        if !rec.reverse_zones.is_empty() {
+            self.update_ptr_print(rec, addrs[0]).await;
+        }
+        UpdateStatus::Done
+    }
+
+    async fn update_record_set(&self, rec: &ZoneRecord, addrs: &[IpAddr]) -> Result<()> {
+        let account = self.account(rec.auth.as_ref());
+        let zone_id = self.record_zone_id(account, rec).await?;
+        let records = account
+            .cf
+            .list_records_of_type(&zone_id, &rec.name, record_type(rec, addrs[0]))
+            .await
+            .context("Failed to list the records")?;
+        let owned = ownership::rewritable(self.ownership.as_ref(), rec.adopt, &records)?;
+        let plan = reconcile(&owned, addrs);
+        let settings = ownership::stamp(self.ownership.as_ref(), &rec.settings);
+
+        // Replace before deleting, so that the set never runs empty.
+        for (record, addr) in plan.updates {
+            account
+                .cf
+                .update_record(&zone_id, &record.id, &rec.name, addr, &settings)
+                .await
+                .context("Failed to update a record of the set")?;
+        }
+        for addr in plan.creates {
+            account
+                .cf
+                .create_record(&zone_id, &rec.name, addr, &settings)
+                .await
+                .context("Failed to create a record of the set")?;
+        }
+        // A stale address left in the set must be retried, the cycle is not done.
+        for record in plan.deletes {
+            account
+                .cf
+                .delete_record(&zone_id, &record.id)
+                .await
+                .context("Failed to delete a stale record of the set")?;
+        }
+        Ok(())
+    }
+}
+
+/// The changes turning `records` into a set holding exactly `addrs`.
+#[derive(Debug)]
+struct Reconciliation<'a> {
+    /// Records to point at a missing address, reusing them rather than deleting and creating.
+    updates: Vec<(&'a DnsRecord, IpAddr)>,
+    creates: Vec<IpAddr>,
+    deletes: Vec<&'a DnsRecord>,
+}
+
+fn reconcile<'a>(records: &[&'a DnsRecord], addrs: &[IpAddr]) -> Reconciliation<'a> {
+    let mut missing: Vec<IpAddr> = Vec::with_capacity(addrs.len());
+    for addr in addrs {
+        if !missing.contains(addr) {
+            missing.push(*addr);
+        }
+    }
+    let mut surplus = Vec::new();
+    for record in records {
+        let found = record
+            .content
+            .address()
+            .and_then(|addr| missing.iter().position(|missing| *missing == addr));
+        match found {
+            Some(i) => {
+                missing.remove(i);
+            }
+            None => surplus.push(*record),
+        }
+    }
+
+    let reused = surplus.len().min(missing.len());
+    let deletes = surplus.split_off(reused);
+    let creates = missing.split_off(reused);
+    Reconciliation {
+        updates: surplus.into_iter().zip(missing).collect(),
+        creates,
+        deletes,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn records(json: &str) -> Vec<DnsRecord> {
+        serde_json::from_str(json).expect("valid records")
+    }
+
+    #[test]
+    fn reconcile_reuses_stale_records() -> Result<()> {
+        let records = records(
+            r#"[
+                {"id": "a", "name": "home.example.com", "type": "AAAA", "content": "2001:db8::1"},
+                {"id": "b", "name": "home.example.com", "type": "AAAA", "content": "2001:db8::2"},
+                {"id": "c", "name": "home.example.com", "type": "AAAA", "content": "2001:db8::1"}
+            ]"#,
+        );
+        let refs: Vec<&DnsRecord> = records.iter().collect();
+
+        // one address kept, one replaced in place, the duplicate goes away
+        let plan = reconcile(&refs, &["2001:db8::1".parse()?, "2001:db8::3".parse()?]);
+        assert_eq!(plan.updates.len(), 1);
+        assert_eq!(plan.updates[0].0.id, "b");
+        assert_eq!(plan.updates[0].1, "2001:db8::3".parse::<IpAddr>()?);
+        assert!(plan.creates.is_empty());
+        assert_eq!(plan.deletes.len(), 1);
+        assert_eq!(plan.deletes[0].id, "c");
+
+        // a grown set keeps the existing records and adds the rest
+        let addrs: Vec<IpAddr> = vec![
+            "2001:db8::1".parse()?,
+            "2001:db8::2".parse()?,
+            "2001:db8::4".parse()?,
+            "2001:db8::5".parse()?,
+        ];
+        let plan = reconcile(&refs[..2], &addrs);
+        assert!(plan.updates.is_empty() && plan.deletes.is_empty());
+        assert_eq!(plan.creates, &addrs[2..]);
+        Ok(())
+    }
+}
@@ -0,0 +1,85 @@
+//! `ip4:` and `ip6:` mechanisms of SPF policies
+
+use std::net::IpAddr;
+
+use anyhow::{Context, Result, bail};
+
+use crate::cloudflare::record::{DnsContent, txt_content, txt_text};
+use crate::config::ZoneRecord;
+use crate::updater::conflict::Conflict;
+use crate::updater::{Updater, ownership};
+use crate::util::spf;
+
+impl Updater {
+    /// Points the `ip4:` or `ip6:` mechanism cf-ddns manages in the SPF policy at `rec.name`
+    /// to `addr`, leaving the rest of the policy untouched.
+    ///
+    /// Mechanisms carry no marker, so the address last written is kept in the ID cache to
+    /// recognize the managed one. When it is unknown, a new mechanism is only added if the policy
+    /// authorizes no other single address of the family.
+    pub(super) async fn update_spf(&self, rec: &ZoneRecord, addr: IpAddr) -> Result<()> {
+        let family = if addr.is_ipv4() { "v4" } else { "v6" };
+        let key = format!("spf:{}:{family}", rec.name);
+        let account = self.account(rec.auth.as_ref());
+        let zone_id = self.record_zone_id(account, rec).await?;
+
+        // IPv4 and IPv6 mechanisms live in the same policy, which must be read again after the
+        // other family changed it.
+        let _guard = self.shared_writes.lock().await;
+        let records = account
+            .cf
+            .list_records_of_type(&zone_id, &rec.name, "TXT")
+            .await
+            .context("Failed to list the records")?;
+        let policies: Vec<_> = records
+            .into_iter()
+            .filter_map(|record| match &record.content {
+                DnsContent::TXT { content } => {
+                    let text = txt_text(content);
+                    spf::is_spf(&text).then_some((record, text))
+                }
+                _ => None,
+            })
+            .collect();
+        let (record, policy) = match policies.as_slice() {
+            [] => bail!(Conflict(
+                "Cannot find an SPF policy, it must be created first".to_owned()
+            )),
+            [policy] => policy,
+            _ => bail!(Conflict(format!(
+                "Found {} SPF policies, only one is allowed",
+                policies.len()
+            ))),
+        };
+        ownership::rewritable(
+            self.ownership.as_ref(),
+            rec.adopt,
+            std::slice::from_ref(record),
+        )?;
+
+        let managed = self
+            .id_cache
+            .borrow()
+            .get_target_value(&key)
+            .and_then(|managed| managed.parse().ok());
+        let rewritten = spf::rewrite(policy, managed, addr)
+            .map_err(|e| Conflict(format!("Cannot update the policy: {e:#}")))?;
+        if rewritten.policy != *policy {
+            let content = DnsContent::TXT {
+                content: txt_content(&rewritten.policy),
+            };
+            account
+                .cf
+                .update_record_content(&zone_id, &record.id, &rec.name, content)
+                .await
+                .context("Failed to update the record")?;
+        }
+
+        let mut cache = self.id_cache.borrow_mut();
+        match rewritten.managed {
+            Some(managed) => cache.save_target_value(key, managed.to_string()),
+            None => cache.remove_target_value(&key),
+        }
+        cache.save()
+    }
+}
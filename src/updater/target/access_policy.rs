@@ -0,0 +1,141 @@
+use std::net::IpAddr;
+
+use anyhow::{Context, Result, bail};
+
+use serde_json::Value;
+
+use crate::cloudflare::access::{ip_rule, rule_network};
+use crate::config::AccessPolicyTarget;
+use crate::updater::Updater;
+use crate::updater::conflict::Conflict;
+use crate::util::network::{parse_cidr, to_cidr};
+
+impl Updater {
+    /// Replaces the `ip` include rule cf-ddns last added to the policy with one matching `addr`.
+    ///
+    /// Rules carry no marker, so the network last written is kept in the ID cache to recognize
+    /// the managed rule. When it is unknown, a new rule is only added if the policy has no other
+    /// single-address rule of the family, which could be a stale one written by cf-ddns.
+    pub(super) async fn update_access_policy(
+        &self,
+        target: &AccessPolicyTarget,
+        addr: IpAddr,
+    ) -> Result<()> {
+        let cf = self.account(None).cf;
+        let family = if addr.is_ipv4() { "v4" } else { "v6" };
+        let key = format!(
+            "access_policy:{}:{}:{}:{family}",
+            target.account_id, target.app_id, target.policy_id
+        );
+        let _guard = self.shared_writes.lock().await;
+        let mut policy = cf
+            .get_access_policy(&target.account_id, &target.app_id, &target.policy_id)
+            .await
+            .context("Failed to get the policy")?;
+
+        let cached = self
+            .id_cache
+            .borrow()
+            .get_target_value(&key)
+            .and_then(parse_cidr);
+        let before = policy.include.clone();
+        let managed = rewrite_include(&mut policy.include, cached, addr)?;
+        if policy.include != before {
+            cf.update_access_policy(
+                &target.account_id,
+                &target.app_id,
+                &target.policy_id,
+                &policy,
+            )
+            .await
+            .context("Failed to update the policy")?;
+        }
+
+        let mut cache = self.id_cache.borrow_mut();
+        match managed {
+            Some(network) => cache.save_target_value(key, network),
+            None => cache.remove_target_value(&key),
+        }
+        cache.save()
+    }
+}
+
+/// Points the `ip` rule of `managed`, the network cf-ddns last wrote, at `addr`, adding a rule
+/// when there is none. Returns the network cf-ddns manages from now on: `None` when another rule
+/// already allows `addr`, as that one must never be taken over.
+fn rewrite_include(
+    include: &mut Vec<Value>,
+    managed: Option<(IpAddr, u8)>,
+    addr: IpAddr,
+) -> Result<Option<String>> {
+    let wanted = (addr, if addr.is_ipv4() { 32 } else { 128 });
+    let network = to_cidr(wanted.0, wanted.1);
+    let matches = |rule: &_, network| rule_network(rule).and_then(parse_cidr) == Some(network);
+    let present = include.iter().any(|rule| matches(rule, wanted));
+    let stale = managed
+        .filter(|&managed| managed != wanted)
+        .and_then(|managed| include.iter().position(|rule| matches(rule, managed)));
+
+    match (present, stale) {
+        (true, None) => {}
+        (true, Some(i)) => {
+            include.remove(i);
+        }
+        (false, Some(i)) => include[i] = ip_rule(&network),
+        (false, None) => {
+            let single_address = |rule: &_| {
+                rule_network(rule)
+                    .and_then(parse_cidr)
+                    .is_some_and(|(ip, len)| ip.is_ipv4() == addr.is_ipv4() && len == wanted.1)
+            };
+            if managed.is_none() && include.iter().any(single_address) {
+                let family = if addr.is_ipv4() { "IPv4" } else { "IPv6" };
+                bail!(Conflict(format!(
+                    "The policy already allows a single {family} address, and the ID cache \
+                     does not record which rule cf-ddns manages. Remove the stale rule"
+                )));
+            }
+            include.push(ip_rule(&network));
+        }
+    }
+    Ok((!present || managed == Some(wanted)).then_some(network))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn networks(include: &[Value]) -> Vec<&str> {
+        include.iter().filter_map(rule_network).collect()
+    }
+
+    #[test]
+    fn rewrite_include_follows_successive_addresses() -> Result<()> {
+        let mut include = vec![ip_rule("198.51.100.7/32"), ip_rule("192.0.2.1/32")];
+        let mut managed = Some(("192.0.2.1".parse()?, 32));
+        for addr in ["203.0.113.5", "203.0.113.6"] {
+            let network = rewrite_include(&mut include, managed, addr.parse()?)?;
+            managed = network.as_deref().and_then(parse_cidr);
+        }
+        assert_eq!(networks(&include), ["198.51.100.7/32", "203.0.113.6/32"]);
+        assert_eq!(managed, Some(("203.0.113.6".parse()?, 32)));
+        Ok(())
+    }
+
+    #[test]
+    fn rewrite_include_never_takes_over_other_rules() -> Result<()> {
+        let mut include = vec![ip_rule("198.51.100.7/32"), ip_rule("192.0.2.1/32")];
+        // the address moves to the one of another rule
+        let managed = rewrite_include(
+            &mut include,
+            Some(("192.0.2.1".parse()?, 32)),
+            "198.51.100.7".parse()?,
+        )?;
+        assert_eq!(managed, None);
+        assert_eq!(networks(&include), ["198.51.100.7/32"]);
+        // and on: the other rule is left alone rather than rewritten
+        assert!(rewrite_include(&mut include, None, "203.0.113.5".parse()?).is_err());
+        assert_eq!(networks(&include), ["198.51.100.7/32"]);
+        Ok(())
+    }
+}
@@ -0,0 +1,65 @@
+use std::net::IpAddr;
+
+use anyhow::{Context, Result, bail};
+
+use crate::cloudflare::access_rule::RuleScope;
+use crate::config::AccessRuleTarget;
+use crate::updater::Updater;
+
+impl Updater {
+    /// Makes sure a rule allowing `addr` exists, and removes the rules left over for previous
+    /// addresses of the same family.
+    pub(super) async fn update_access_rule(
+        &self,
+        target: &AccessRuleTarget,
+        addr: IpAddr,
+    ) -> Result<()> {
+        let account = self.account(None);
+        let zone_id;
+        let scope = match (&target.account_id, &target.zone_id, &target.zone) {
+            (Some(id), _, _) => RuleScope::Account(id),
+            (None, Some(id), _) => RuleScope::Zone(id),
+            (None, None, Some(zone)) => {
+                zone_id = self
+                    .zone_id(account, zone)
+                    .await
+                    .context("Failed to get the zone identifier")?;
+                RuleScope::Zone(&zone_id)
+            }
+            (None, None, None) => bail!("No zone or account to apply the rule to"),
+        };
+
+        // The API matches notes by substring, so only exact matches are ours.
+        let rules: Vec<_> = account
+            .cf
+            .list_access_rules(scope, &target.notes, addr)
+            .await
+            .context("Failed to list the rules")?
+            .into_iter()
+            .filter(|rule| rule.notes == target.notes)
+            .collect();
+        if !rules
+            .iter()
+            .any(|rule| rule.configuration.addr() == Some(addr))
+        {
+            account
+                .cf
+                .create_access_rule(scope, &target.notes, addr)
+                .await
+                .context("Failed to create the rule")?;
+        }
+        // Stale rules are only removed once the current address is allowed,
+        // so access is never lost in between.
+        for rule in rules
+            .iter()
+            .filter(|rule| rule.configuration.addr() != Some(addr))
+        {
+            account
+                .cf
+                .delete_access_rule(scope, &rule.id)
+                .await
+                .context("Failed to delete the stale rule")?;
+        }
+        Ok(())
+    }
+}
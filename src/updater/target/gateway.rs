@@ -0,0 +1,49 @@
+use std::net::IpAddr;
+
+use anyhow::{Context, Result};
+
+use crate::cloudflare::gateway::LocationNetwork;
+use crate::config::GatewayLocationTarget;
+use crate::updater::Updater;
+use crate::util::network::{parse_cidr, to_cidr};
+
+impl Updater {
+    /// Replaces the location networks of the same family as `addr` with the network of `addr`.
+    pub(super) async fn update_gateway_location(
+        &self,
+        target: &GatewayLocationTarget,
+        addr: IpAddr,
+    ) -> Result<()> {
+        let cf = self.account(None).cf;
+        let _guard = self.shared_writes.lock().await;
+        let mut location = cf
+            .get_gateway_location(&target.account_id, &target.location_id)
+            .await
+            .context("Failed to get the location")?;
+
+        let network = match addr {
+            IpAddr::V4(_) => to_cidr(addr, 32),
+            IpAddr::V6(_) => to_cidr(addr, target.ipv6_prefix_len),
+        };
+        let same_family = |n: &LocationNetwork| {
+            parse_cidr(&n.network).is_some_and(|(ip, _)| ip.is_ipv4() == addr.is_ipv4())
+        };
+        let current: Vec<_> = location
+            .networks
+            .iter()
+            .filter(|n| same_family(n))
+            .map(|n| parse_cidr(&n.network))
+            .collect();
+        if current == [parse_cidr(&network)] {
+            return Ok(());
+        }
+
+        // Networks of the other family are kept as they are.
+        location.networks.retain(|n| !same_family(n));
+        location.networks.push(LocationNetwork { network });
+        cf.update_gateway_location(&target.account_id, &target.location_id, &location)
+            .await
+            .context("Failed to update the location")?;
+        Ok(())
+    }
+}
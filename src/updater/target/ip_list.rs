@@ -0,0 +1,48 @@
+use std::net::IpAddr;
+
+use anyhow::{Context, Result};
+
+use crate::cloudflare::list::item_ip;
+use crate::config::IpListTarget;
+use crate::updater::Updater;
+use crate::util::network::parse_cidr;
+
+impl Updater {
+    /// Makes sure the list holds an item for `addr`, and removes the items left over for
+    /// previous addresses of the same family.
+    pub(super) async fn update_ip_list(&self, target: &IpListTarget, addr: IpAddr) -> Result<()> {
+        let cf = self.account(None).cf;
+        let wanted = parse_cidr(&item_ip(addr));
+        let items = cf
+            .list_items(&target.account_id, &target.list_id)
+            .await
+            .context("Failed to list the items")?;
+        let owned: Vec<_> = items
+            .iter()
+            .filter(|item| item.comment.as_deref() == Some(target.comment.as_str()))
+            .filter(|item| {
+                item.network()
+                    .is_some_and(|(ip, _)| ip.is_ipv4() == addr.is_ipv4())
+            })
+            .collect();
+
+        if !owned.iter().any(|item| item.network() == wanted) {
+            cf.create_list_item(&target.account_id, &target.list_id, addr, &target.comment)
+                .await
+                .context("Failed to add the item")?;
+        }
+        // Stale items are only removed once the current address is listed,
+        // so rules referencing the list never miss it in between.
+        let stale: Vec<_> = owned
+            .iter()
+            .filter(|item| item.network() != wanted)
+            .map(|item| item.id.as_str())
+            .collect();
+        if !stale.is_empty() {
+            cf.delete_list_items(&target.account_id, &target.list_id, &stale)
+                .await
+                .context("Failed to remove the stale items")?;
+        }
+        Ok(())
+    }
+}
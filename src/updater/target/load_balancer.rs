@@ -0,0 +1,35 @@
+use std::net::IpAddr;
+
+use anyhow::{Context, Result, anyhow};
+
+use crate::config::LoadBalancerOriginTarget;
+use crate::updater::Updater;
+
+impl Updater {
+    /// Points the origin at `addr`, leaving the other origins of the pool untouched.
+    pub(super) async fn update_load_balancer_origin(
+        &self,
+        target: &LoadBalancerOriginTarget,
+        addr: IpAddr,
+    ) -> Result<()> {
+        let cf = self.account(None).cf;
+        let mut pool = cf
+            .get_pool(&target.account_id, &target.pool_id)
+            .await
+            .context("Failed to get the pool")?;
+        let origin = pool
+            .origins
+            .iter_mut()
+            .find(|origin| origin.name == target.origin)
+            .ok_or_else(|| anyhow!("Cannot find origin '{}' in the pool", target.origin))?;
+        if origin.address.parse() == Ok(addr) {
+            return Ok(());
+        }
+
+        origin.address = addr.to_string();
+        cf.update_pool_origins(&target.account_id, &target.pool_id, &pool.origins)
+            .await
+            .context("Failed to update the pool")?;
+        Ok(())
+    }
+}
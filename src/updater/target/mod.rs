@@ -0,0 +1,65 @@
+//! Cloudflare resources other than DNS records that track the current address
+
+use std::net::IpAddr;
+
+use anyhow::Result;
+use futures::future::join_all;
+use tracing::{error, info};
+
+use crate::cloudflare::error;
+use crate::config::{Target, TargetKind};
+use crate::updater::{UpdateStatus, Updater, conflict};
+
+mod access_policy;
+mod access_rule;
+mod gateway;
+mod ip_list;
+mod load_balancer;
+mod workers_kv;
+
+impl Updater {
+    /// Updates `targets` to `addr`. Returns the outcome for each target, in the same order.
+    pub(super) async fn update_targets(
+        &self,
+        targets: &[&Target],
+        addr: IpAddr,
+    ) -> Vec<UpdateStatus> {
+        join_all(targets.iter().map(|target| async move {
+            info!("Updating {target} to {addr}");
+            match self.update_target(target, addr).await {
+                Ok(()) => {
+                    info!("Updated {target}");
+                    UpdateStatus::Done
+                }
+                Err(e) => report_failure(target, &e),
+            }
+        }))
+        .await
+    }
+
+    async fn update_target(&self, target: &Target, addr: IpAddr) -> Result<()> {
+        match &target.kind {
+            TargetKind::AccessRule(rule) => self.update_access_rule(rule, addr).await,
+            TargetKind::IpList(list) => self.update_ip_list(list, addr).await,
+            TargetKind::GatewayLocation(location) => {
+                self.update_gateway_location(location, addr).await
+            }
+            TargetKind::LoadBalancerOrigin(origin) => {
+                self.update_load_balancer_origin(origin, addr).await
+            }
+            TargetKind::AccessPolicy(policy) => self.update_access_policy(policy, addr).await,
+            TargetKind::WorkersKv(kv) => self.update_workers_kv(target, kv, addr).await,
+        }
+    }
+}
+
+/// Logs a failed target update and decides whether it is worth retrying.
+fn report_failure(target: &Target, e: &anyhow::Error) -> UpdateStatus {
+    if error::is_permanent(e) || conflict::is_conflict(e) {
+        error!("Failed to update {target}: {e:#} (not retrying)");
+        UpdateStatus::Failed
+    } else {
+        error!("Failed to update {target}: {e:#}");
+        UpdateStatus::Retry
+    }
+}
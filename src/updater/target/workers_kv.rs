@@ -0,0 +1,65 @@
+use std::net::IpAddr;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use anyhow::{Context, Result};
+
+use crate::cloudflare::kv::AddressValue;
+use crate::config::{Target, WorkersKvTarget};
+use crate::updater::Updater;
+
+impl Updater {
+    /// Writes the value with `addr` and the last address published for the other family.
+    ///
+    /// The addresses written are kept in the ID cache, as each family is updated on its own.
+    pub(super) async fn update_workers_kv(
+        &self,
+        target: &Target,
+        kv: &WorkersKvTarget,
+        addr: IpAddr,
+    ) -> Result<()> {
+        let key = |family| {
+            format!(
+                "workers_kv:{}:{}:{}:{family}",
+                kv.account_id, kv.namespace_id, kv.key
+            )
+        };
+        let (v4_key, v6_key) = (key("v4"), key("v6"));
+        let _guard = self.shared_writes.lock().await;
+
+        let (own_key, other_key, other_enabled) = match addr {
+            IpAddr::V4(_) => (v4_key, v6_key, target.v6),
+            IpAddr::V6(_) => (v6_key, v4_key, target.v4),
+        };
+        let other: Option<IpAddr> = if other_enabled {
+            let cache = self.id_cache.borrow();
+            cache
+                .get_target_value(&other_key)
+                .and_then(|a| a.parse().ok())
+        } else {
+            None
+        };
+        let (v4, v6) = if addr.is_ipv4() {
+            (Some(addr), other)
+        } else {
+            (other, Some(addr))
+        };
+        let value = AddressValue {
+            v4,
+            v6,
+            timestamp: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_secs(),
+            hostname: &kv.hostname,
+        };
+        self.account(None)
+            .cf
+            .write_kv_value(&kv.account_id, &kv.namespace_id, &kv.key, value)
+            .await
+            .context("Failed to write the value")?;
+
+        let mut cache = self.id_cache.borrow_mut();
+        cache.save_target_value(own_key, addr.to_string());
+        cache.save()
+    }
+}
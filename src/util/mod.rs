@@ -1,2 +1,6 @@
 pub(crate) mod ip_ext;
 pub mod matcher;
+pub mod network;
+pub mod spf;
+pub mod svcb;
+pub mod time;
@@ -0,0 +1,131 @@
+//! Networks written in CIDR notation, as used by several Cloudflare APIs, and reverse DNS names.
+
+use std::fmt::Write;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+
+/// The network of prefix length `len` containing `addr`, in CIDR notation.
+pub fn to_cidr(addr: IpAddr, len: u8) -> String {
+    let network = match addr {
+        IpAddr::V4(addr) => {
+            let mask = u32::MAX.checked_shl(32_u32.saturating_sub(len.into()));
+            IpAddr::V4(Ipv4Addr::from_bits(addr.to_bits() & mask.unwrap_or(0)))
+        }
+        IpAddr::V6(addr) => {
+            let mask = u128::MAX.checked_shl(128_u32.saturating_sub(len.into()));
+            IpAddr::V6(Ipv6Addr::from_bits(addr.to_bits() & mask.unwrap_or(0)))
+        }
+    };
+    format!("{network}/{len}")
+}
+
+/// Parses an address or a network in CIDR notation; a bare address is a network of its full
+/// length.
+pub fn parse_cidr(s: &str) -> Option<(IpAddr, u8)> {
+    if let Some((addr, len)) = s.split_once('/') {
+        return Some((addr.parse().ok()?, len.parse().ok()?));
+    }
+    let addr: IpAddr = s.parse().ok()?;
+    let len = if addr.is_ipv4() { 32 } else { 128 };
+    Some((addr, len))
+}
+
+/// The name of the PTR record of `addr`, under `in-addr.arpa` or `ip6.arpa`.
+pub fn reverse_name(addr: IpAddr) -> String {
+    let mut name = String::new();
+    match addr {
+        IpAddr::V4(addr) => {
+            for octet in addr.octets().iter().rev() {
+                let _ = write!(name, "{octet}.");
+            }
+            name.push_str("in-addr.arpa");
+        }
+        IpAddr::V6(addr) => {
+            for octet in addr.octets().iter().rev() {
+                let _ = write!(name, "{:x}.{:x}.", octet & 0xf, octet >> 4);
+            }
+            name.push_str("ip6.arpa");
+        }
+    }
+    name
+}
+
+/// The most specific of `zones` that `name` belongs to, comparing names case-insensitively.
+pub fn longest_zone<'a>(zones: impl IntoIterator<Item = &'a str>, name: &str) -> Option<&'a str> {
+    zones
+        .into_iter()
+        .filter(|zone| in_zone(name, zone))
+        .max_by_key(|zone| zone.len())
+}
+
+/// Whether `name` is `zone` or below it, on a label boundary.
+fn in_zone(name: &str, zone: &str) -> bool {
+    let Some(split) = name.len().checked_sub(zone.len()) else {
+        return false;
+    };
+    name.get(split..)
+        .is_some_and(|suffix| suffix.eq_ignore_ascii_case(zone))
+        && (split == 0 || name.as_bytes()[split - 1] == b'.')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn to_cidr_masks_host_bits() -> anyhow::Result<()> {
+        assert_eq!(to_cidr("203.0.113.5".parse()?, 32), "203.0.113.5/32");
+        assert_eq!(to_cidr("203.0.113.5".parse()?, 24), "203.0.113.0/24");
+        assert_eq!(
+            to_cidr("2001:db8:1:2:3:4:5:6".parse()?, 64),
+            "2001:db8:1:2::/64"
+        );
+        assert_eq!(to_cidr("2001:db8:1:2:3:4:5:6".parse()?, 0), "::/0");
+        Ok(())
+    }
+
+    #[test]
+    fn parse_cidr_accepts_bare_addresses() -> anyhow::Result<()> {
+        let v4: IpAddr = "203.0.113.5".parse()?;
+        assert_eq!(parse_cidr("203.0.113.5"), Some((v4, 32)));
+        assert_eq!(parse_cidr("203.0.113.5/32"), Some((v4, 32)));
+        let v6: IpAddr = "2001:db8:1:2::".parse()?;
+        assert_eq!(parse_cidr("2001:db8:1:2::/64"), Some((v6, 64)));
+        assert_eq!(parse_cidr("not an ip"), None);
+        Ok(())
+    }
+
+    #[test]
+    fn reverse_name_of_addresses() -> anyhow::Result<()> {
+        assert_eq!(
+            reverse_name("203.0.113.5".parse()?),
+            "5.113.0.203.in-addr.arpa"
+        );
+        assert_eq!(
+            reverse_name("2001:db8::567:89ab".parse()?),
+            "b.a.9.8.7.6.5.0.0.0.0.0.0.0.0.0.0.0.0.0.0.0.0.0.8.b.d.0.1.0.0.2.ip6.arpa"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn longest_zone_picks_most_specific_suffix() {
+        let zones = [
+            "example.com",
+            "lab.example.com",
+            "0.203.in-addr.arpa",
+            "113.0.203.in-addr.arpa",
+        ];
+        assert_eq!(
+            longest_zone(zones, "Host.Lab.example.com"),
+            Some("lab.example.com")
+        );
+        assert_eq!(longest_zone(zones, "example.com"), Some("example.com"));
+        assert_eq!(
+            longest_zone(zones, "5.113.0.203.in-addr.arpa"),
+            Some("113.0.203.in-addr.arpa")
+        );
+        // suffixes only match on label boundaries
+        assert_eq!(longest_zone(zones, "myexample.com"), None);
+        assert_eq!(longest_zone(zones, "5.10.203.in-addr.arpa"), None);
+    }
+}
@@ -0,0 +1,292 @@
+//! SPF policies (RFC 7208), as held by TXT records, e.g. `v=spf1 mx ip4:192.0.2.1 -all`.
+
+use std::net::IpAddr;
+
+use anyhow::{Result, bail, ensure};
+
+use crate::util::network::parse_cidr;
+
+const VERSION: &str = "v=spf1";
+
+/// Whether `text` is an SPF policy, as opposed to any other TXT record.
+pub fn is_spf(text: &str) -> bool {
+    text.get(..VERSION.len())
+        .is_some_and(|v| v.eq_ignore_ascii_case(VERSION))
+        && text[VERSION.len()..]
+            .chars()
+            .next()
+            .is_none_or(|c| c == ' ')
+}
+
+/// A policy rewritten for a new address.
+#[derive(Debug, PartialEq, Eq)]
+pub struct Rewritten {
+    pub policy: String,
+    /// The address of the mechanism cf-ddns manages from now on. `None` when another mechanism
+    /// already authorizes the address, as that one must never be taken over.
+    pub managed: Option<IpAddr>,
+}
+
+/// Replaces the `ip4:`/`ip6:` mechanism of `managed` with one for `addr`, adding it when
+/// there is none. Other mechanisms and modifiers are left as they are.
+///
+/// With `managed` unknown, a mechanism is only added if the policy authorizes no other single
+/// address of the family, as that could be a stale one that would stay authorized for good.
+/// A new mechanism goes before `all` or `redirect=`, which would otherwise make it unreachable.
+pub fn rewrite(policy: &str, managed: Option<IpAddr>, addr: IpAddr) -> Result<Rewritten> {
+    let mechanism = format!("{}:{addr}", ip_mechanism(addr));
+    let mut terms = terms(policy)?;
+    let present = terms.iter().any(|term| ip_term(term) == Some(addr));
+    let stale = managed
+        .filter(|&managed| managed != addr)
+        .and_then(|managed| terms.iter().position(|term| ip_term(term) == Some(managed)));
+    match (present, stale) {
+        (true, None) => {}
+        (true, Some(i)) => {
+            terms.remove(i);
+        }
+        (false, Some(i)) => terms[i] = &mechanism,
+        (false, None) => {
+            let same_family =
+                |term: &&&str| ip_term(term).is_some_and(|ip| ip.is_ipv4() == addr.is_ipv4());
+            if managed.is_none()
+                && let Some(other) = terms.iter().find(same_family)
+            {
+                bail!(
+                    "the policy already authorizes '{other}', and it is not known which mechanism \
+                     cf-ddns manages; remove the stale one"
+                );
+            }
+            let at = terms
+                .iter()
+                .position(|term| is_final(term))
+                .unwrap_or(terms.len());
+            terms.insert(at, &mechanism);
+        }
+    }
+
+    let rewritten = std::iter::once(VERSION)
+        .chain(terms)
+        .collect::<Vec<_>>()
+        .join(" ");
+    validate(&rewritten)?;
+    Ok(Rewritten {
+        policy: rewritten,
+        managed: (!present || managed == Some(addr)).then_some(addr),
+    })
+}
+
+/// Checks that `policy` is syntactically valid SPF.
+pub fn validate(policy: &str) -> Result<()> {
+    let mut redirect = false;
+    let mut exp = false;
+    for term in terms(policy)? {
+        if let Some((name, _)) = term.split_once('=')
+            && !name.contains(':')
+        {
+            ensure!(
+                name.starts_with(|c: char| c.is_ascii_alphabetic())
+                    && name
+                        .chars()
+                        .all(|c| c.is_ascii_alphanumeric() || "-_.".contains(c)),
+                "invalid SPF modifier '{term}'"
+            );
+            let seen = match name.to_ascii_lowercase().as_str() {
+                "redirect" => &mut redirect,
+                "exp" => &mut exp,
+                _ => continue,
+            };
+            ensure!(!*seen, "duplicate SPF modifier '{name}'");
+            *seen = true;
+        } else {
+            validate_mechanism(term)?;
+        }
+    }
+    Ok(())
+}
+
+fn validate_mechanism(term: &str) -> Result<()> {
+    let mechanism = term.trim_start_matches(['+', '-', '~', '?']);
+    ensure!(
+        term.len() - mechanism.len() <= 1,
+        "invalid SPF qualifier in '{term}'"
+    );
+    let (name, arg) = match mechanism.split_once([':', '/']) {
+        Some((name, _)) => (name, Some(&mechanism[name.len()..])),
+        None => (mechanism, None),
+    };
+    match (name.to_ascii_lowercase().as_str(), arg) {
+        ("all", None) | ("a" | "mx" | "ptr", _) => {}
+        ("include" | "exists", Some(arg)) if arg.len() > 1 && arg.starts_with(':') => {}
+        ("ip4" | "ip6", Some(arg)) => {
+            let Some((ip, len)) = arg.strip_prefix(':').and_then(parse_cidr) else {
+                bail!("invalid address in SPF mechanism '{term}'");
+            };
+            ensure!(
+                ip.is_ipv4() == name.eq_ignore_ascii_case("ip4")
+                    && len <= if ip.is_ipv4() { 32 } else { 128 },
+                "invalid address in SPF mechanism '{term}'"
+            );
+        }
+        _ => bail!("invalid SPF mechanism '{term}'"),
+    }
+    Ok(())
+}
+
+/// The terms following the version, which must lead the policy.
+fn terms(policy: &str) -> Result<Vec<&str>> {
+    ensure!(
+        is_spf(policy),
+        "not an SPF policy, expected '{VERSION}' first"
+    );
+    Ok(policy[VERSION.len()..].split_whitespace().collect())
+}
+
+const fn ip_mechanism(addr: IpAddr) -> &'static str {
+    match addr {
+        IpAddr::V4(_) => "ip4",
+        IpAddr::V6(_) => "ip6",
+    }
+}
+
+/// The address of an `ip4:`/`ip6:` mechanism matching a single address.
+fn ip_term(term: &str) -> Option<IpAddr> {
+    let (name, value) = term.trim_start_matches('+').split_once(':')?;
+    let (ip, len) = parse_cidr(value)?;
+    let single = if ip.is_ipv4() { 32 } else { 128 };
+    (name.eq_ignore_ascii_case(ip_mechanism(ip)) && len == single).then_some(ip)
+}
+
+/// Whether no mechanism after `term` is ever evaluated.
+fn is_final(term: &str) -> bool {
+    let lower = term.to_ascii_lowercase();
+    lower.trim_start_matches(['+', '-', '~', '?']) == "all" || lower.starts_with("redirect=")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rewritten(policy: &str, managed: Option<IpAddr>) -> Rewritten {
+        Rewritten {
+            policy: policy.to_owned(),
+            managed,
+        }
+    }
+
+    #[test]
+    fn rewrite_replaces_managed_mechanism() -> Result<()> {
+        let policy = "v=spf1 mx ip4:192.0.2.1 ip4:198.51.100.7 -all";
+        let addr = "203.0.113.5".parse()?;
+        assert_eq!(
+            rewrite(policy, Some("192.0.2.1".parse()?), addr)?,
+            rewritten(
+                "v=spf1 mx ip4:203.0.113.5 ip4:198.51.100.7 -all",
+                Some(addr)
+            )
+        );
+        // already listed by another mechanism: the managed one goes away, and is not replaced
+        // by the other one
+        assert_eq!(
+            rewrite(policy, Some("192.0.2.1".parse()?), "198.51.100.7".parse()?)?,
+            rewritten("v=spf1 mx ip4:198.51.100.7 -all", None)
+        );
+        // the own mechanism is still managed
+        assert_eq!(
+            rewrite(policy, Some("192.0.2.1".parse()?), "192.0.2.1".parse()?)?,
+            rewritten(policy, Some("192.0.2.1".parse()?))
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn rewrite_follows_successive_addresses() -> Result<()> {
+        let mut policy = "v=spf1 mx ip4:198.51.100.7 ip4:192.0.2.1 -all".to_owned();
+        let mut managed = Some("192.0.2.1".parse()?);
+        for addr in ["203.0.113.5", "203.0.113.6"] {
+            let result = rewrite(&policy, managed, addr.parse()?)?;
+            (policy, managed) = (result.policy, result.managed);
+        }
+        assert_eq!(policy, "v=spf1 mx ip4:198.51.100.7 ip4:203.0.113.6 -all");
+        assert_eq!(managed, Some("203.0.113.6".parse()?));
+        Ok(())
+    }
+
+    #[test]
+    fn rewrite_never_takes_over_other_mechanisms() -> Result<()> {
+        let policy = "v=spf1 ip4:198.51.100.7 ip4:192.0.2.1 -all";
+        // the address moves to the one of another mechanism
+        let first = rewrite(policy, Some("192.0.2.1".parse()?), "198.51.100.7".parse()?)?;
+        assert_eq!(first, rewritten("v=spf1 ip4:198.51.100.7 -all", None));
+        // and on: the other mechanism is left alone rather than rewritten
+        assert!(rewrite(&first.policy, first.managed, "203.0.113.5".parse()?).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn rewrite_adds_before_all() -> Result<()> {
+        assert_eq!(
+            rewrite("v=spf1 mx ~all", None, "2001:db8::5".parse()?)?.policy,
+            "v=spf1 mx ip6:2001:db8::5 ~all"
+        );
+        assert_eq!(
+            rewrite(
+                "v=spf1 redirect=_spf.example.com",
+                None,
+                "203.0.113.5".parse()?
+            )?
+            .policy,
+            "v=spf1 ip4:203.0.113.5 redirect=_spf.example.com"
+        );
+        assert_eq!(
+            rewrite("v=spf1", None, "203.0.113.5".parse()?)?.policy,
+            "v=spf1 ip4:203.0.113.5"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn rewrite_refuses_to_add_next_to_unknown_mechanism() -> Result<()> {
+        let policy = "v=spf1 ip4:192.0.2.1 ip6:2001:db8::/32 -all";
+        assert!(rewrite(policy, None, "203.0.113.5".parse()?).is_err());
+        // a range is never one cf-ddns wrote
+        assert_eq!(
+            rewrite(policy, None, "2001:db8::5".parse()?)?.policy,
+            "v=spf1 ip4:192.0.2.1 ip6:2001:db8::/32 ip6:2001:db8::5 -all"
+        );
+        // the managed mechanism is gone, so there is nothing stale to replace
+        assert_eq!(
+            rewrite(
+                policy,
+                Some("198.51.100.7".parse()?),
+                "203.0.113.5".parse()?
+            )?
+            .policy,
+            "v=spf1 ip4:192.0.2.1 ip6:2001:db8::/32 ip4:203.0.113.5 -all"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn validate_rejects_malformed_policies() {
+        assert!(validate("v=spf1 a mx:mail.example.com/24 include:_spf.example.com ?all").is_ok());
+        assert!(
+            validate("v=spf1 ip4:192.0.2.0/24 ip6:2001:db8::/32 exp=explain.example.com").is_ok()
+        );
+        assert!(validate("v=spf10 -all").is_err());
+        assert!(validate("v=spf1 ip4:2001:db8::1").is_err());
+        assert!(validate("v=spf1 ip4:192.0.2.1/33").is_err());
+        assert!(validate("v=spf1 include:").is_err());
+        assert!(validate("v=spf1 +-all").is_err());
+        assert!(validate("v=spf1 redirect=a.example redirect=b.example").is_err());
+        assert!(validate("v=spf1 bogus").is_err());
+    }
+
+    #[test]
+    fn is_spf_requires_exact_version() {
+        assert!(is_spf("v=spf1 -all"));
+        assert!(is_spf("V=SPF1"));
+        assert!(!is_spf("v=spf10"));
+        assert!(!is_spf("google-site-verification=abc"));
+    }
+}
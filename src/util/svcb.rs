@@ -0,0 +1,114 @@
+//! Parameters of SVCB and HTTPS records, in presentation format (RFC 9460), e.g.
+//! `alpn="h3,h2" ipv4hint="192.0.2.1"`.
+
+/// Sets parameter `key` to `value`, leaving the other parameters as they are.
+///
+/// An existing parameter is replaced in place; a new one is inserted before the first parameter
+/// with a higher key number, as presentation format lists them in ascending order.
+pub fn set_param(params: &str, key: &str, value: &str) -> String {
+    let param = format!("{key}=\"{value}\"");
+    let mut split = split_params(params);
+    if let Some(existing) = split.iter_mut().find(|p| param_key(p) == key) {
+        *existing = &param;
+    } else {
+        let number = key_number(key);
+        let at = split
+            .iter()
+            .position(|p| key_number(param_key(p)) > number)
+            .unwrap_or(split.len());
+        split.insert(at, &param);
+    }
+    split.join(" ")
+}
+
+/// Splits on whitespace outside of quoted values.
+fn split_params(params: &str) -> Vec<&str> {
+    let mut split = Vec::new();
+    let mut start = None;
+    let mut quoted = false;
+    let mut escaped = false;
+    for (i, c) in params.char_indices() {
+        match c {
+            _ if escaped => escaped = false,
+            '\\' => escaped = true,
+            '"' => quoted = !quoted,
+            c if c.is_whitespace() && !quoted => {
+                if let Some(s) = start.take() {
+                    split.push(&params[s..i]);
+                }
+                continue;
+            }
+            _ => {}
+        }
+        start.get_or_insert(i);
+    }
+    if let Some(s) = start {
+        split.push(&params[s..]);
+    }
+    split
+}
+
+fn param_key(param: &str) -> &str {
+    param.split_once('=').map_or(param, |(key, _)| key)
+}
+
+/// Sorts after every valid key number.
+const UNKNOWN_KEY: u32 = 1 << 16;
+
+/// The numeric value of a parameter key, [`UNKNOWN_KEY`] for unrecognized keys.
+fn key_number(key: &str) -> u32 {
+    match key {
+        "mandatory" => 0,
+        "alpn" => 1,
+        "no-default-alpn" => 2,
+        "port" => 3,
+        "ipv4hint" => 4,
+        "ech" => 5,
+        "ipv6hint" => 6,
+        _ => key
+            .strip_prefix("key")
+            .and_then(|n| n.parse::<u16>().ok())
+            .map_or(UNKNOWN_KEY, u32::from),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn set_param_replaces_in_place() {
+        assert_eq!(
+            set_param(
+                r#"alpn="h3,h2" ipv4hint="192.0.2.1" ipv6hint="2001:db8::1""#,
+                "ipv4hint",
+                "203.0.113.5"
+            ),
+            r#"alpn="h3,h2" ipv4hint="203.0.113.5" ipv6hint="2001:db8::1""#
+        );
+    }
+
+    #[test]
+    fn set_param_inserts_in_key_order() {
+        assert_eq!(
+            set_param(r#"alpn="h3" ech="AEn+""#, "ipv4hint", "203.0.113.5"),
+            r#"alpn="h3" ipv4hint="203.0.113.5" ech="AEn+""#
+        );
+        assert_eq!(
+            set_param("alpn=h2 no-default-alpn", "ipv6hint", "2001:db8::5"),
+            r#"alpn=h2 no-default-alpn ipv6hint="2001:db8::5""#
+        );
+        assert_eq!(
+            set_param("", "ipv4hint", "203.0.113.5"),
+            r#"ipv4hint="203.0.113.5""#
+        );
+    }
+
+    #[test]
+    fn split_params_keeps_quoted_whitespace() {
+        assert_eq!(
+            split_params(r#" alpn="h3, h2"  key65000="a \" b" port=443"#),
+            [r#"alpn="h3, h2""#, r#"key65000="a \" b""#, "port=443"]
+        );
+    }
+}
@@ -0,0 +1,51 @@
+//! Timestamps in RFC 3339 format, without pulling in a date-time crate.
+
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Formats `time` as an RFC 3339 UTC timestamp with second precision,
+/// e.g. `2026-01-01T00:00:00Z`. Times before the Unix epoch are clamped to it.
+pub fn rfc3339(time: SystemTime) -> String {
+    let secs = time
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs();
+    let (days, secs) = (secs / 86_400, secs % 86_400);
+    let (year, month, day) = civil_from_days(days);
+    format!(
+        "{year:04}-{month:02}-{day:02}T{:02}:{:02}:{:02}Z",
+        secs / 3600,
+        secs % 3600 / 60,
+        secs % 60
+    )
+}
+
+/// Converts days since the Unix epoch to a proleptic Gregorian date, using
+/// [Howard Hinnant's algorithm](https://howardhinnant.github.io/date_algorithms.html#civil_from_days).
+const fn civil_from_days(days: u64) -> (u64, u64, u64) {
+    let z = days + 719_468;
+    let era = z / 146_097;
+    let doe = z % 146_097;
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+    (year, month, day)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+
+    #[test]
+    fn rfc3339_formats_utc() {
+        assert_eq!(rfc3339(UNIX_EPOCH), "1970-01-01T00:00:00Z");
+        let leap_day = UNIX_EPOCH + Duration::from_secs(1_709_210_096);
+        assert_eq!(rfc3339(leap_day), "2024-02-29T12:34:56Z");
+        let new_year = UNIX_EPOCH + Duration::from_secs(1_767_225_599);
+        assert_eq!(rfc3339(new_year), "2025-12-31T23:59:59Z");
+    }
+}